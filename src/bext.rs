@@ -0,0 +1,117 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::serialize::{BinaryDeserialize, BinarySerialize};
+
+// Version 1 of the chunk, without coding history
+const BEXT_SIZE: usize = 602;
+
+/// Broadcast Wave `bext` chunk. Text fields are ASCII, longer ones are cut to the size of their
+/// field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bext {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    /// `YYYY-MM-DD`
+    pub origination_date: String,
+    /// `HH:MM:SS`
+    pub origination_time: String,
+    /// Frames since midnight at the first sample.
+    pub time_reference: u64,
+}
+
+impl Bext {
+    /// Origination date, time and time reference of a recording starting at `time`, in UTC.
+    pub fn originated_at(time: SystemTime, sample_rate: u32) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let days = since_epoch.as_secs() / 86400;
+        let seconds = since_epoch.as_secs() % 86400;
+        let (year, month, day) = civil_from_days(days as i64);
+        let time_reference = seconds * sample_rate as u64
+            + since_epoch.subsec_nanos() as u64 * sample_rate as u64 / 1_000_000_000;
+
+        Self {
+            origination_date: format!("{:04}-{:02}-{:02}", year, month, day),
+            origination_time: format!(
+                "{:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ),
+            time_reference,
+            ..Self::default()
+        }
+    }
+
+    /// Parses the body of a `bext` chunk, `None` if it is too small.
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        if body.len() < 346 {
+            return None;
+        }
+
+        let text = |field: &[u8]| {
+            let text = field.split(|&b| b == 0).next().unwrap_or_default();
+            String::from_utf8_lossy(text).into_owned()
+        };
+
+        Some(Self {
+            description: text(&body[0..256]),
+            originator: text(&body[256..288]),
+            originator_reference: text(&body[288..320]),
+            origination_date: text(&body[320..330]),
+            origination_time: text(&body[330..338]),
+            time_reference: u64::deserialize(&body[338..346]).unwrap(),
+        })
+    }
+}
+
+// Proleptic Gregorian date of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so that the leap day is last
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month as u32, day as u32)
+}
+
+impl BinarySerialize for Bext {
+    fn needed_size(&self) -> usize {
+        8 + BEXT_SIZE
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..4].copy_from_slice(b"bext");
+        (BEXT_SIZE as u32).serialize(&mut buffer[4..8])?;
+        let body = &mut buffer[8..8 + BEXT_SIZE];
+        body.fill(0);
+
+        let fields = [
+            (&self.description, 0..256),
+            (&self.originator, 256..288),
+            (&self.originator_reference, 288..320),
+            (&self.origination_date, 320..330),
+            (&self.origination_time, 330..338),
+        ];
+        for (text, range) in fields {
+            let len = text.len().min(range.len());
+            body[range.start..range.start + len].copy_from_slice(&text.as_bytes()[..len]);
+        }
+        self.time_reference.serialize(&mut body[338..346])?;
+        // Version, then the UMID and reserved bytes are left zeroed
+        1u16.serialize(&mut body[346..348])?;
+
+        Ok(())
+    }
+}
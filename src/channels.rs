@@ -0,0 +1,147 @@
+use std::time::{Instant, SystemTime};
+
+use crate::{
+    source::{AudioSource, Gap, SourceError},
+    wav::WavFormat,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Downmix {
+    /// Mean of the channels, never clips.
+    Average,
+    /// Sum of the channels, attenuated by this many dB.
+    Sum { headroom_db: f32 },
+}
+
+/// Which input channels end up in which output channel, and with which gain.
+#[derive(Debug, Clone)]
+pub struct ChannelMap {
+    input_channels: usize,
+    // The input channels and gains summed into each output channel
+    outputs: Vec<Vec<(usize, f32)>>,
+}
+
+impl ChannelMap {
+    /// Keeps the `selection` input channels (0-based, in this order, possibly repeated), or all of
+    /// them, then mixes them down to mono if `downmix` is set.
+    ///
+    /// Panics if a selected channel doesn't exist.
+    pub fn new(input_channels: u16, selection: Option<&[u16]>, downmix: Option<Downmix>) -> Self {
+        let selection = match selection {
+            Some(selection) => selection.iter().map(|&c| c as usize).collect(),
+            None => (0..input_channels as usize).collect::<Vec<_>>(),
+        };
+        assert!(
+            !selection.is_empty() && selection.iter().all(|&c| c < input_channels as usize),
+            "invalid channel selection"
+        );
+
+        let outputs = match downmix {
+            None => selection.into_iter().map(|c| vec![(c, 1.0)]).collect(),
+            Some(downmix) => {
+                let gain = match downmix {
+                    Downmix::Average => 1.0 / selection.len() as f32,
+                    Downmix::Sum { headroom_db } => 10f32.powf(-headroom_db / 20.0),
+                };
+                vec![selection.into_iter().map(|c| (c, gain)).collect()]
+            }
+        };
+
+        Self {
+            input_channels: input_channels as usize,
+            outputs,
+        }
+    }
+
+    pub fn output_channels(&self) -> u16 {
+        self.outputs.len() as u16
+    }
+
+    /// Maps the whole frames of `input` into `output`, which must hold as many frames.
+    pub fn apply(&self, input: &[f32], output: &mut [f32]) {
+        let frames = input.chunks_exact(self.input_channels);
+        for (frame, out) in frames.zip(output.chunks_exact_mut(self.outputs.len())) {
+            for (sample, sources) in out.iter_mut().zip(&self.outputs) {
+                *sample = sources.iter().map(|&(c, gain)| frame[c] * gain).sum();
+            }
+        }
+    }
+}
+
+/// Applies a `ChannelMap` to another source.
+pub struct MappedSource {
+    inner: Box<dyn AudioSource>,
+    map: ChannelMap,
+    input: Vec<f32>,
+}
+
+impl MappedSource {
+    pub fn new(inner: Box<dyn AudioSource>, map: ChannelMap) -> Self {
+        assert_eq!(inner.channels() as usize, map.input_channels);
+
+        Self {
+            inner,
+            map,
+            input: Vec::new(),
+        }
+    }
+}
+
+impl AudioSource for MappedSource {
+    fn channels(&self) -> u16 {
+        self.map.output_channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn native_format(&self) -> (WavFormat, u16) {
+        self.inner.native_format()
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        let frames = buffer.len() / self.map.outputs.len();
+        self.input.resize(frames * self.map.input_channels, 0.0);
+
+        let len = match self.inner.read(&mut self.input)? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        let frames = len / self.map.input_channels;
+        self.map.apply(
+            &self.input[..len],
+            &mut buffer[..frames * self.map.outputs.len()],
+        );
+
+        Ok(Some(frames * self.map.outputs.len()))
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+
+    fn take_dropped(&mut self) -> u64 {
+        self.inner.take_dropped()
+    }
+
+    fn take_gaps(&mut self) -> Vec<Gap> {
+        self.inner.take_gaps()
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.inner.take_errors()
+    }
+
+    fn last_capture(&self) -> Option<Instant> {
+        self.inner.last_capture()
+    }
+
+    fn start_time(&self) -> Option<SystemTime> {
+        self.inner.start_time()
+    }
+}
@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use record_wav::channels::Downmix;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Record audio from an input device into a .wav file",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub record: RecordArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the available hosts, their devices and supported stream configurations
+    ListDevices {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct RecordArgs {
+    /// What to record: `device`, a `sine[:FREQ]` or `noise` generator, or `file:PATH` to replay
+    /// a .wav file
+    #[arg(short, long, default_value = "device", value_parser = parse_source)]
+    pub source: SourceKind,
+
+    /// Audio host to use, as printed by `list-devices` [default: the default host]
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// Input device to record from, by exact name or substring [default: the default device]
+    #[arg(short, long)]
+    pub device: Option<String>,
+
+    /// Path of the .wav file to write
+    #[arg(short, long, default_value = "out.wav")]
+    pub output: PathBuf,
+
+    /// Write each channel to its own mono file, named after the output with the channel number,
+    /// e.g. `out_1.wav`, `out_2.wav`...
+    #[arg(long)]
+    pub split: bool,
+
+    /// Sample rate of the output file in Hz, audio captured at another rate is resampled
+    /// [default: the capture rate, 48000 for generators]
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub rate: Option<u32>,
+
+    /// Sample rate to capture or generate at in Hz [default: --rate if the device supports it,
+    /// otherwise the highest rate it supports]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub device_rate: Option<u32>,
+
+    /// Quality of the sample rate conversion
+    #[arg(long, value_enum, default_value_t = ResampleQuality::High)]
+    pub resample_quality: ResampleQuality,
+
+    /// Number of channels to capture [default: the first configuration of the device, 2 for
+    /// generators]
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub channels: Option<u16>,
+
+    /// Input channels to keep, counting from 1, in the order they are written to the file. A
+    /// channel can be repeated, e.g. `3,4` or `1,1`
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    pub map: Option<Vec<u16>>,
+
+    /// Mix the (mapped) channels down to mono: `average`, or `sum[:DB]` attenuated by DB
+    /// decibels of headroom (6 by default)
+    #[arg(long, value_parser = parse_downmix)]
+    pub downmix: Option<Downmix>,
+
+    /// Bits per sample of the output file [default: 16, or 32 for float]
+    #[arg(short, long, value_parser = parse_bits)]
+    pub bits: Option<u16>,
+
+    /// Sample format of the output file [default: the native format of the device]
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Dither added when reducing the bit depth [default: tpdf when the output has less
+    /// resolution than the captured or processed audio]
+    #[arg(long, value_enum)]
+    pub dither: Option<DitherMode>,
+
+    /// Shape the dither noise towards less audible frequencies
+    #[arg(long, value_enum, default_value_t = NoiseShapingMode::None)]
+    pub noise_shaping: NoiseShapingMode,
+
+    /// Seed of the dither noise, the same seed gives the same file
+    #[arg(long, default_value_t = 0)]
+    pub dither_seed: u64,
+
+    /// Fill the gaps detected in the capture with silence, so the file keeps the same timeline as
+    /// the device clock. Gaps are marked with cue points either way
+    #[arg(long)]
+    pub fill_gaps: bool,
+
+    /// What to do when the input device fails or is unplugged: stop, wait for the `same` device
+    /// to come back, or fall back to the `default` device if it doesn't
+    #[arg(long, value_enum, default_value_t = ReconnectPolicy::None)]
+    pub reconnect: ReconnectPolicy,
+
+    /// How long to keep trying to reconnect, in seconds
+    #[arg(long, default_value_t = 30.0, value_parser = parse_duration)]
+    pub reconnect_timeout: f64,
+
+    /// After reconnecting, continue in a new file (`out-2.wav`, `out-3.wav`...) that may have
+    /// another channel count or sample rate, instead of in the same file
+    #[arg(long)]
+    pub segment_on_reconnect: bool,
+
+    /// Stand by until Enter is pressed, then start the take with this many seconds of audio from
+    /// before
+    #[arg(long, value_parser = parse_duration)]
+    pub pre_roll: Option<f64>,
+
+    /// Mark every point where recording resumes after a pause with a cue
+    #[arg(long)]
+    pub cue_on_resume: bool,
+
+    /// Don't show the level meters, they are only shown when stderr is a terminal anyway
+    #[arg(long)]
+    pub no_meter: bool,
+
+    /// Title tag of the file (`INAM`)
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Artist tag of the file (`IART`)
+    #[arg(long)]
+    pub artist: Option<String>,
+
+    /// Comment tag of the file (`ICMT`)
+    #[arg(long)]
+    pub comment: Option<String>,
+
+    /// Creation date tag of the file (`ICRD`), e.g. 2024-05-01. Defaults to the UTC date the
+    /// recording starts
+    #[arg(long)]
+    pub date: Option<String>,
+
+    /// Description of the Broadcast Wave `bext` chunk
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Also write the end of recording report to this file as JSON
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    /// Stop recording after this many seconds
+    #[arg(short = 't', long, value_parser = parse_duration)]
+    pub duration: Option<f64>,
+}
+
+#[derive(Clone)]
+pub enum SourceKind {
+    Device,
+    Sine(f64),
+    Noise,
+    File(PathBuf),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Integer PCM
+    Int,
+    /// 32-bit IEEE float
+    Float,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ResampleQuality {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DitherMode {
+    None,
+    /// Triangular probability density function dither of 2 LSB peak to peak
+    Tpdf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NoiseShapingMode {
+    None,
+    /// Pushes the noise towards high frequencies
+    FirstOrder,
+    /// 5-tap E-weighted filter, designed for 44.1 kHz
+    Lipshitz,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReconnectPolicy {
+    /// Stop recording
+    None,
+    /// Wait for the same device to come back
+    Same,
+    /// Wait for the same device, or use the default input device
+    Default,
+}
+
+fn parse_source(value: &str) -> Result<SourceKind, String> {
+    match value.split_once(':') {
+        None if value == "device" => Ok(SourceKind::Device),
+        None if value == "sine" => Ok(SourceKind::Sine(440.0)),
+        None if value == "noise" => Ok(SourceKind::Noise),
+        Some(("sine", frequency)) => match frequency.parse::<f64>() {
+            Ok(frequency) if frequency > 0.0 && frequency.is_finite() => {
+                Ok(SourceKind::Sine(frequency))
+            }
+            _ => Err("expected a positive sine frequency in Hz".to_string()),
+        },
+        Some(("file", path)) if !path.is_empty() => Ok(SourceKind::File(path.into())),
+        _ => Err("expected device, sine[:FREQ], noise or file:PATH".to_string()),
+    }
+}
+
+fn parse_downmix(value: &str) -> Result<Downmix, String> {
+    match value.split_once(':') {
+        None if value == "average" => Ok(Downmix::Average),
+        None if value == "sum" => Ok(Downmix::Sum { headroom_db: 6.0 }),
+        Some(("sum", headroom)) => match headroom.parse::<f32>() {
+            Ok(headroom_db) if headroom_db >= 0.0 && headroom_db.is_finite() => {
+                Ok(Downmix::Sum { headroom_db })
+            }
+            _ => Err("expected a headroom of zero or more dB".to_string()),
+        },
+        _ => Err("expected average or sum[:DB]".to_string()),
+    }
+}
+
+fn parse_bits(value: &str) -> Result<u16, String> {
+    match value.parse() {
+        Ok(bits @ (16 | 24 | 32)) => Ok(bits),
+        _ => Err("expected 16, 24 or 32".to_string()),
+    }
+}
+
+fn parse_duration(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Ok(seconds),
+        _ => Err("expected a positive number of seconds".to_string()),
+    }
+}
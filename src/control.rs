@@ -0,0 +1,59 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+/// Commands for a `Recorder`, given from other threads while it runs.
+#[derive(Debug, Default)]
+pub struct Control {
+    stop: AtomicBool,
+    armed: AtomicBool,
+    paused: AtomicBool,
+    // Notes of the markers asked for since the recorder last looked
+    markers: Mutex<Vec<Option<String>>>,
+}
+
+impl Control {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Starts a recorder that stands by with a pre-roll.
+    pub fn arm(&self) {
+        self.armed.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::Relaxed)
+    }
+
+    /// Stops appending samples to the files, the source keeps running.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Marks the current position of the recording, with an optional note.
+    pub fn add_marker(&self, note: Option<String>) {
+        self.markers.lock().unwrap().push(note);
+    }
+
+    pub(crate) fn take_markers(&self) -> Vec<Option<String>> {
+        std::mem::take(&mut self.markers.lock().unwrap())
+    }
+}
@@ -0,0 +1,91 @@
+use crate::serialize::{text_size, BinarySerialize};
+
+/// A labelled position in the recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    /// Frame offset from the start of the `data` chunk.
+    pub frame: u32,
+    pub label: String,
+    /// Longer text, e.g. what happened there.
+    pub note: Option<String>,
+}
+
+/// Cue points, stored as a `cue ` chunk followed by a `LIST`/`adtl` chunk with their labels and
+/// notes.
+/// Nothing is written when there are none.
+#[derive(Debug, Clone, Default)]
+pub struct Cues(pub Vec<Cue>);
+
+impl Cues {
+    fn cue_size(&self) -> usize {
+        4 + 24 * self.0.len()
+    }
+
+    fn adtl_size(&self) -> usize {
+        4 + self
+            .0
+            .iter()
+            .map(|cue| {
+                12 + text_size(&cue.label)
+                    + cue.note.as_deref().map_or(0, |note| 12 + text_size(note))
+            })
+            .sum::<usize>()
+    }
+}
+
+impl BinarySerialize for Cues {
+    fn needed_size(&self) -> usize {
+        if self.0.is_empty() {
+            return 0;
+        }
+
+        8 + self.cue_size() + 8 + self.adtl_size()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        buffer[0..4].copy_from_slice(b"cue ");
+        (self.cue_size() as u32).serialize(&mut buffer[4..8])?;
+        (self.0.len() as u32).serialize(&mut buffer[8..12])?;
+        let mut off = 12;
+        for (id, cue) in (1u32..).zip(&self.0) {
+            id.serialize(&mut buffer[off..off + 4])?;
+            // Play order position, then where the cue is: sample `frame` of the `data` chunk
+            cue.frame.serialize(&mut buffer[off + 4..off + 8])?;
+            buffer[off + 8..off + 12].copy_from_slice(b"data");
+            0u32.serialize(&mut buffer[off + 12..off + 16])?;
+            0u32.serialize(&mut buffer[off + 16..off + 20])?;
+            cue.frame.serialize(&mut buffer[off + 20..off + 24])?;
+            off += 24;
+        }
+
+        buffer[off..off + 4].copy_from_slice(b"LIST");
+        (self.adtl_size() as u32).serialize(&mut buffer[off + 4..off + 8])?;
+        buffer[off + 8..off + 12].copy_from_slice(b"adtl");
+        off += 12;
+        for (id, cue) in (1u32..).zip(&self.0) {
+            let texts = [(b"labl", Some(&cue.label)), (b"note", cue.note.as_ref())];
+            for (kind, text) in texts
+                .into_iter()
+                .filter_map(|(kind, text)| Some((kind, text?)))
+            {
+                let size = text_size(text);
+                buffer[off..off + 4].copy_from_slice(kind);
+                ((4 + text.len() + 1) as u32).serialize(&mut buffer[off + 4..off + 8])?;
+                id.serialize(&mut buffer[off + 8..off + 12])?;
+                let body = &mut buffer[off + 12..off + 12 + size];
+                body.fill(0);
+                body[..text.len()].copy_from_slice(text.as_bytes());
+                off += 12 + size;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    iter, mem,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use cpal::{
+    traits::{DeviceTrait, StreamTrait},
+    SampleFormat,
+};
+
+use crate::{
+    ring::{ring_buffer, Consumer, Producer},
+    sample::Sample,
+    source::{AudioSource, Gap, SourceError},
+    wav::WavFormat,
+};
+
+// Gaps the callback can report before the recorder picks them up, it must not allocate
+const MAX_PENDING_GAPS: usize = 64;
+
+// Some backends never report unplugged devices, their stream just stops calling back
+const STALL_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Filled by the stream callbacks, emptied by the recorder
+struct Shared {
+    errors: Mutex<Vec<String>>,
+    gaps: Mutex<Vec<Gap>>,
+    // Nanoseconds from `created` to the last callback
+    last_callback: AtomicU64,
+    created: Instant,
+    failed: AtomicBool,
+}
+
+impl Shared {
+    fn last_callback(&self) -> Instant {
+        self.created + Duration::from_nanos(self.last_callback.load(Ordering::Relaxed))
+    }
+}
+
+/// Captures from a cpal input device. The audio callback pushes into a lock-free ring buffer
+/// that `read` drains, so the realtime thread never waits on the rest of the pipeline.
+pub struct CpalSource {
+    stream: Option<cpal::Stream>,
+    consumer: Consumer,
+    channels: u16,
+    sample_rate: u32,
+    sample_format: SampleFormat,
+    shared: Arc<Shared>,
+    start_time: SystemTime,
+}
+
+impl CpalSource {
+    /// Starts capturing right away, the ring buffer holds `buffer_seconds` of audio. Gaps in the
+    /// capture timestamps are reported, and filled with silence if `fill_gaps` is set.
+    pub fn new(
+        device: &cpal::Device,
+        config: &cpal::SupportedStreamConfig,
+        buffer_seconds: f32,
+        fill_gaps: bool,
+    ) -> Result<Self, SourceError> {
+        let stream_config = config.config();
+        let capacity = (stream_config.sample_rate.0 as f32 * buffer_seconds) as usize
+            * stream_config.channels as usize;
+        let (producer, consumer) = ring_buffer(capacity.max(1));
+        let shared = Arc::new(Shared {
+            errors: Mutex::new(Vec::new()),
+            gaps: Mutex::new(Vec::with_capacity(MAX_PENDING_GAPS)),
+            last_callback: AtomicU64::new(0),
+            created: Instant::now(),
+            failed: AtomicBool::new(false),
+        });
+
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => build_input_stream::<f32>(
+                device,
+                &stream_config,
+                producer,
+                shared.clone(),
+                fill_gaps,
+            ),
+            SampleFormat::I16 => build_input_stream::<i16>(
+                device,
+                &stream_config,
+                producer,
+                shared.clone(),
+                fill_gaps,
+            ),
+            SampleFormat::U16 => build_input_stream::<u16>(
+                device,
+                &stream_config,
+                producer,
+                shared.clone(),
+                fill_gaps,
+            ),
+        }?;
+        let start_time = SystemTime::now();
+        stream
+            .play()
+            .map_err(|err| SourceError::Stream(err.to_string()))?;
+
+        Ok(Self {
+            stream: Some(stream),
+            consumer,
+            channels: stream_config.channels,
+            sample_rate: stream_config.sample_rate.0,
+            sample_format: config.sample_format(),
+            shared,
+            start_time,
+        })
+    }
+}
+
+impl AudioSource for CpalSource {
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn native_format(&self) -> (WavFormat, u16) {
+        match self.sample_format {
+            SampleFormat::F32 => (WavFormat::IeeeFloat, 32),
+            SampleFormat::I16 | SampleFormat::U16 => (WavFormat::Pcm, 16),
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        true
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        // The ring only ever holds whole callback blocks, so whole frames
+        let len = self.consumer.pop(buffer);
+        if len > 0 {
+            return Ok(Some(len));
+        }
+        if self.stream.is_none() {
+            return Ok(None);
+        }
+
+        // What was captured before a failure is read first
+        if self.shared.failed.load(Ordering::Relaxed)
+            || self.shared.last_callback().elapsed() > STALL_TIMEOUT
+        {
+            return Err(SourceError::Disconnected);
+        }
+
+        Ok(Some(0))
+    }
+
+    fn stop(&mut self) {
+        // Dropping the stream waits for the callback, nothing is pushed afterwards
+        self.stream = None;
+    }
+
+    fn take_dropped(&mut self) -> u64 {
+        self.consumer.take_dropped()
+    }
+
+    fn take_gaps(&mut self) -> Vec<Gap> {
+        // Drained rather than taken so the callback never has to allocate
+        self.shared.gaps.lock().unwrap().drain(..).collect()
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        mem::take(&mut *self.shared.errors.lock().unwrap())
+    }
+
+    fn last_capture(&self) -> Option<Instant> {
+        Some(self.shared.last_callback())
+    }
+
+    fn start_time(&self) -> Option<SystemTime> {
+        Some(self.start_time)
+    }
+}
+
+/// A sample type cpal can deliver, scaled to `f32` the same way as the .wav sample types.
+trait InputSample: cpal::Sample + Send + 'static {
+    fn to_f32(self) -> f32;
+}
+
+impl InputSample for i16 {
+    fn to_f32(self) -> f32 {
+        Sample::to_f32(self)
+    }
+}
+
+impl InputSample for u16 {
+    fn to_f32(self) -> f32 {
+        // Unsigned samples are centered on 32768
+        Sample::to_f32((self ^ 0x8000) as i16)
+    }
+}
+
+impl InputSample for f32 {
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+fn build_input_stream<S: InputSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut producer: Producer,
+    shared: Arc<Shared>,
+    fill_gaps: bool,
+) -> Result<cpal::Stream, SourceError> {
+    let channels = config.channels as usize;
+    let sample_rate = config.sample_rate.0 as f64;
+    // Frames pushed so far and when the next callback should have been captured
+    let mut position = 0u64;
+    let mut next_capture: Option<cpal::StreamInstant> = None;
+    let mut pending_gaps = Vec::with_capacity(MAX_PENDING_GAPS);
+    let callback_shared = shared.clone();
+
+    let data_fn = move |data: &[S], info: &cpal::InputCallbackInfo| {
+        let since_created = callback_shared.created.elapsed().as_nanos() as u64;
+        callback_shared
+            .last_callback
+            .store(since_created, Ordering::Relaxed);
+        let frames = data.len() / channels;
+        let capture = info.timestamp().capture;
+
+        let late = next_capture.and_then(|expected| capture.duration_since(&expected));
+        let missing = late.map_or(0, |late| {
+            (late.as_secs_f64() * sample_rate).round() as usize
+        });
+        // Timestamps jitter a bit, only being more than half a callback late is a gap
+        if missing > 0 && missing * 2 > frames {
+            let silence = missing * channels;
+            let filled = fill_gaps && producer.free() >= silence;
+            if filled {
+                producer.push(iter::repeat_n(0.0, silence));
+            }
+            if pending_gaps.len() < pending_gaps.capacity() {
+                pending_gaps.push(Gap {
+                    frame: position,
+                    frames: missing as u64,
+                    filled,
+                });
+            }
+            if filled {
+                position += missing as u64;
+            }
+        }
+        next_capture = capture.add(Duration::from_secs_f64(frames as f64 / sample_rate));
+
+        // Overflows are counted by the ring and reported by the reading side
+        if producer.push(data.iter().map(|&sample| InputSample::to_f32(sample))) {
+            position += frames as u64;
+        }
+
+        // Never wait for the recorder, the gaps are handed over on a later callback instead
+        if !pending_gaps.is_empty() {
+            if let Ok(mut gaps) = callback_shared.gaps.try_lock() {
+                let room = MAX_PENDING_GAPS - gaps.len();
+                let count = pending_gaps.len().min(room);
+                gaps.extend(pending_gaps.drain(..count));
+            }
+        }
+    };
+
+    // Not called from the realtime thread, it can lock
+    let err_fn = move |err: cpal::StreamError| {
+        eprintln!("an error occurred on the audio stream: {}", err);
+        if let cpal::StreamError::DeviceNotAvailable = err {
+            shared.failed.store(true, Ordering::Relaxed);
+        }
+        shared.errors.lock().unwrap().push(err.to_string());
+    };
+
+    device
+        .build_input_stream(config, data_fn, err_fn)
+        .map_err(|err| SourceError::Stream(err.to_string()))
+}
@@ -0,0 +1,141 @@
+use cpal::{
+    traits::{DeviceTrait, HostTrait},
+    SupportedBufferSize, SupportedStreamConfigRange,
+};
+use serde_json::{json, Value};
+
+pub fn list_devices(json: bool) -> Result<(), String> {
+    let default_host_id = cpal::default_host().id();
+
+    let hosts = cpal::available_hosts()
+        .into_iter()
+        .map(|host_id| {
+            // A host that can't be used (e.g. no JACK server running) is listed with its error
+            let devices = match host_devices(host_id) {
+                Ok(devices) => devices,
+                Err(err) => return json!({ "name": host_id.name(), "error": err }),
+            };
+
+            json!({
+                "name": host_id.name(),
+                "default": host_id == default_host_id,
+                "devices": devices,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hosts).unwrap());
+    } else {
+        print_table(&hosts);
+    }
+
+    Ok(())
+}
+
+fn host_devices(host_id: cpal::HostId) -> Result<Vec<Value>, String> {
+    let host = cpal::host_from_id(host_id).map_err(|err| err.to_string())?;
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+
+    let devices = host
+        .devices()
+        .map_err(|err| err.to_string())?
+        .map(|device| {
+            let name = device.name().unwrap_or_else(|_| "<unknown>".to_string());
+            // Devices that can't be opened in a direction just have no configs for it
+            let inputs = device
+                .supported_input_configs()
+                .map(|configs| configs.map(|range| config_json(&range)).collect())
+                .unwrap_or_default();
+            let outputs = device
+                .supported_output_configs()
+                .map(|configs| configs.map(|range| config_json(&range)).collect())
+                .unwrap_or_default();
+
+            json!({
+                "name": name,
+                "default_input": default_input.as_ref() == Some(&name),
+                "default_output": default_output.as_ref() == Some(&name),
+                "input_configs": Value::Array(inputs),
+                "output_configs": Value::Array(outputs),
+            })
+        })
+        .collect();
+
+    Ok(devices)
+}
+
+fn config_json(range: &SupportedStreamConfigRange) -> Value {
+    let buffer_size = match range.buffer_size() {
+        SupportedBufferSize::Range { min, max } => json!({ "min": min, "max": max }),
+        SupportedBufferSize::Unknown => Value::Null,
+    };
+
+    json!({
+        "channels": range.channels(),
+        "min_sample_rate": range.min_sample_rate().0,
+        "max_sample_rate": range.max_sample_rate().0,
+        "sample_format": format!("{:?}", range.sample_format()),
+        "buffer_size": buffer_size,
+    })
+}
+
+fn print_table(hosts: &[Value]) {
+    for host in hosts {
+        let default = if host["default"] == true {
+            " (default)"
+        } else {
+            ""
+        };
+        println!("{}{}", host["name"].as_str().unwrap(), default);
+        if let Some(err) = host["error"].as_str() {
+            println!("  unavailable: {}", err);
+            continue;
+        }
+
+        for device in host["devices"].as_array().unwrap() {
+            let mut flags = Vec::new();
+            if device["default_input"] == true {
+                flags.push("default input");
+            }
+            if device["default_output"] == true {
+                flags.push("default output");
+            }
+            let flags = if flags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", flags.join(", "))
+            };
+            println!("  \"{}\"{}", device["name"].as_str().unwrap(), flags);
+
+            for (direction, key) in [("input ", "input_configs"), ("output", "output_configs")] {
+                for config in device[key].as_array().unwrap() {
+                    println!("    {} {}", direction, format_config(config));
+                }
+            }
+        }
+    }
+}
+
+fn format_config(config: &Value) -> String {
+    let min_rate = config["min_sample_rate"].as_u64().unwrap();
+    let max_rate = config["max_sample_rate"].as_u64().unwrap();
+    let rates = if min_rate == max_rate {
+        format!("{} Hz", min_rate)
+    } else {
+        format!("{}-{} Hz", min_rate, max_rate)
+    };
+    let buffer_size = match &config["buffer_size"] {
+        Value::Null => "unknown".to_string(),
+        range => format!("{}-{} frames", range["min"], range["max"]),
+    };
+
+    format!(
+        "{:>2} ch  {:<16} {:<4} buffer {}",
+        config["channels"].as_u64().unwrap(),
+        rates,
+        config["sample_format"].as_str().unwrap(),
+        buffer_size
+    )
+}
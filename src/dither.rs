@@ -0,0 +1,77 @@
+use crate::rng::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseShaping {
+    None,
+    /// Feeds back the last error, pushing the noise towards high frequencies.
+    FirstOrder,
+    /// Lipshitz's 5-tap E-weighted filter, moves the noise away from where hearing is most
+    /// sensitive. Designed for 44.1 kHz.
+    Lipshitz,
+}
+
+impl NoiseShaping {
+    fn coefficients(self) -> &'static [f32] {
+        match self {
+            NoiseShaping::None => &[],
+            NoiseShaping::FirstOrder => &[1.0],
+            NoiseShaping::Lipshitz => &[2.033, -2.165, 1.959, -1.590, 0.6149],
+        }
+    }
+}
+
+const MAX_ORDER: usize = 5;
+
+/// Adds triangular (TPDF) dither, optionally noise shaped, before samples are quantized to
+/// fewer bits. The same seed gives the same output.
+pub struct Dither {
+    shaping: NoiseShaping,
+    rng: Rng,
+    // Last quantization errors of each channel, the most recent first
+    errors: Vec<[f32; MAX_ORDER]>,
+    channel: usize,
+}
+
+impl Dither {
+    pub fn new(channels: u16, shaping: NoiseShaping, seed: u64) -> Self {
+        Self {
+            shaping,
+            rng: Rng::new(seed),
+            errors: vec![[0.0; MAX_ORDER]; channels.max(1) as usize],
+            channel: 0,
+        }
+    }
+
+    /// Quantizes the next interleaved sample to `bits`, the result is still in the [-1.0, 1.0]
+    /// range but exactly representable on `bits`.
+    pub fn process(&mut self, value: f32, bits: u16) -> f32 {
+        let scale = (1i64 << (bits - 1)) as f32;
+        let channel = self.channel;
+        self.channel = (channel + 1) % self.errors.len();
+        let errors = &mut self.errors[channel];
+
+        let feedback: f32 = self
+            .shaping
+            .coefficients()
+            .iter()
+            .zip(errors.iter())
+            .map(|(c, e)| c * e)
+            .sum();
+        let shaped = value * scale - feedback;
+
+        // Difference of two uniform values, triangular between -1 and 1 LSB
+        let noise = self.rng.next_f32() - self.rng.next_f32();
+        let quantized = (shaped + noise).round();
+        let clipped = quantized.clamp(-scale, scale - 1.0);
+
+        // Clipping errors are not fed back, they would make the filter ring
+        errors.copy_within(0..MAX_ORDER - 1, 1);
+        errors[0] = if clipped == quantized {
+            quantized - shaped
+        } else {
+            0.0
+        };
+
+        clipped / scale
+    }
+}
@@ -0,0 +1,100 @@
+use crate::serialize::{text_size, BinaryDeserialize, BinarySerialize};
+
+/// Tags of a `LIST`/`INFO` chunk, only the ones that are set are written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    /// `INAM`
+    pub title: Option<String>,
+    /// `IART`
+    pub artist: Option<String>,
+    /// `ICMT`
+    pub comment: Option<String>,
+    /// `ICRD`, usually a `YYYY-MM-DD` date.
+    pub creation_date: Option<String>,
+    /// `ISFT`
+    pub software: Option<String>,
+}
+
+impl Info {
+    pub fn is_empty(&self) -> bool {
+        self.tags().next().is_none()
+    }
+
+    /// Parses the body of a `LIST` chunk of type `INFO`, after the list type. Unknown tags are
+    /// ignored.
+    pub fn parse(mut body: &[u8]) -> Self {
+        let mut info = Self::default();
+        while body.len() >= 8 {
+            let size = u32::deserialize(&body[4..8]).unwrap() as usize;
+            let text = &body[8..(8 + size).min(body.len())];
+            let text = text.split(|&b| b == 0).next().unwrap_or_default();
+            let text = Some(String::from_utf8_lossy(text).into_owned());
+            match &body[0..4] {
+                b"INAM" => info.title = text,
+                b"IART" => info.artist = text,
+                b"ICMT" => info.comment = text,
+                b"ICRD" => info.creation_date = text,
+                b"ISFT" => info.software = text,
+                _ => {}
+            }
+
+            body = body.get(8 + size + (size & 1)..).unwrap_or_default();
+        }
+
+        info
+    }
+
+    fn tags(&self) -> impl Iterator<Item = (&[u8; 4], &str)> {
+        [
+            (b"INAM", &self.title),
+            (b"IART", &self.artist),
+            (b"ICMT", &self.comment),
+            (b"ICRD", &self.creation_date),
+            (b"ISFT", &self.software),
+        ]
+        .into_iter()
+        .filter_map(|(id, text)| Some((id, text.as_deref()?)))
+    }
+
+    fn list_size(&self) -> usize {
+        4 + self
+            .tags()
+            .map(|(_, text)| 8 + text_size(text))
+            .sum::<usize>()
+    }
+}
+
+impl BinarySerialize for Info {
+    fn needed_size(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+
+        8 + self.list_size()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        buffer[0..4].copy_from_slice(b"LIST");
+        (self.list_size() as u32).serialize(&mut buffer[4..8])?;
+        buffer[8..12].copy_from_slice(b"INFO");
+        let mut off = 12;
+        for (id, text) in self.tags() {
+            let size = text_size(text);
+            buffer[off..off + 4].copy_from_slice(id);
+            ((text.len() + 1) as u32).serialize(&mut buffer[off + 4..off + 8])?;
+            let body = &mut buffer[off + 8..off + 8 + size];
+            body.fill(0);
+            body[..text.len()].copy_from_slice(text.as_bytes());
+            off += 8 + size;
+        }
+
+        Ok(())
+    }
+}
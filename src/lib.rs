@@ -0,0 +1,19 @@
+pub mod bext;
+pub mod channels;
+pub mod control;
+pub mod cue;
+pub mod device;
+pub mod dither;
+pub mod info;
+pub mod meter;
+pub mod reader;
+pub mod recorder;
+pub mod report;
+pub mod resample;
+pub mod ring;
+pub mod rng;
+pub mod sample;
+pub mod serialize;
+pub mod source;
+pub mod wav;
+pub mod writer;
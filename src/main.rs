@@ -1,243 +1,639 @@
 use std::{
-    io::Write,
-    sync::{Arc, Mutex},
+    fs::File,
+    io::{BufWriter, IsTerminal},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
+use clap::Parser;
 use cpal::{
-    traits::{DeviceTrait, HostTrait, StreamTrait},
+    traits::{DeviceTrait, HostTrait},
     SampleFormat,
 };
 
-fn main() {
-    let host = cpal::default_host();
-    let input_device = host
-        .default_input_device()
-        .expect("no input device available");
-
-    println!("Using input device: \"{}\"", input_device.name().unwrap());
-
-    let mut supported_configs_range = input_device
-        .supported_input_configs()
-        .expect("error while querying configs");
-    let supported_config = supported_configs_range
-        .find(|supported_range| return supported_range.sample_format() == SampleFormat::I16)
-        .expect("no supported config?!")
-        .with_max_sample_rate();
-    let config = supported_config.config();
-
-    let file = Arc::new(Mutex::new(WavFile::new(
-        config.channels,
-        config.sample_rate.0,
-    )));
-
-    let file_thread = file.clone();
-    let err_fn = |err| eprintln!("an error occurred on the audio stream: {}", err);
-    let input_stream = input_device
-        .build_input_stream(
-            &config,
-            move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                let mut file = file_thread.lock().unwrap();
-                for &sample in data {
-                    file.push_sample(sample);
-                }
-            },
-            err_fn,
-        )
-        .unwrap();
-
-    input_stream.play().expect("failed to play input stream");
+use record_wav::{
+    bext::Bext,
+    channels::{ChannelMap, MappedSource},
+    control::Control,
+    device::CpalSource,
+    dither::{Dither, NoiseShaping},
+    info::Info,
+    meter::LevelMeter,
+    recorder::{RecordError, Recorder},
+    resample::{Quality, ResampledSource},
+    source::{AudioSource, FileSource, NoiseSource, SineSource, SourceError},
+    wav::{WavFile, WavFormat},
+    writer::WavWriter,
+};
 
-    // println!("Press Ctrl+C to quit...");
-    // loop {}
+use crate::cli::{
+    Cli, Command, DitherMode, NoiseShapingMode, OutputFormat, ReconnectPolicy, RecordArgs,
+    ResampleQuality, SourceKind,
+};
 
-    std::io::stdin().read_line(&mut String::new()).unwrap();
+mod cli;
+mod devices;
+mod summary;
 
-    let file = file.lock().unwrap();
-    let mut raw = vec![0u8; file.needed_size()];
-    file.serialize(&mut raw)
-        .expect("Failed to serialize .wav file");
+// Room for this much audio between the audio callback and the recorder
+const RING_BUFFER_SECONDS: f32 = 2.0;
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+const GENERATOR_SAMPLE_RATE: u32 = 48000;
+const GENERATOR_CHANNELS: u16 = 2;
+const GENERATOR_AMPLITUDE: f32 = 0.5;
 
-    let mut output = std::fs::File::create("out.wav").expect("failed to create output file");
-    output
-        .write_all(&raw)
-        .expect("failed to write data to file");
+fn main() {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Some(Command::ListDevices { json }) => devices::list_devices(json),
+        None => record(&cli.record),
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
 }
 
-struct WavFile {
+// Settings shared by every segment of a recording
+struct Output {
     format: WavFormat,
-    channels: u16,
-    sample_rate: u32,
     bits_per_sample: u16,
-    samples: Vec<i16>,
+    dither: bool,
+    shaping: NoiseShaping,
+    info: Info,
 }
 
-impl WavFile {
-    fn new(channels: u16, sample_rate: u32) -> Self {
-        Self {
-            format: WavFormat::PCM,
-            channels,
-            sample_rate,
-            bits_per_sample: 16,
-            samples: Vec::new(),
+fn record(cli: &RecordArgs) -> Result<(), String> {
+    let output_format = output_sample_format(cli)?;
+    let (source, device_name) = open_source(cli, output_format)?;
+    let native_format = source.native_format();
+    let native_rate = source.sample_rate();
+    let mut source = build_pipeline(cli, source, cli.rate)?;
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+
+    let (format, bits_per_sample) = output_format.unwrap_or(native_format);
+    // Samples that were mixed or resampled are not on the grid of the native format anymore
+    let processed = cli.downmix.is_some() || sample_rate != native_rate;
+    let dither = match cli.dither {
+        Some(mode) => mode == DitherMode::Tpdf,
+        None => {
+            format == WavFormat::Pcm
+                && (processed
+                    || native_format.0 == WavFormat::IeeeFloat
+                    || native_format.1 > bits_per_sample)
         }
+    };
+    let shaping = match cli.noise_shaping {
+        NoiseShapingMode::None => NoiseShaping::None,
+        NoiseShapingMode::FirstOrder => NoiseShaping::FirstOrder,
+        NoiseShapingMode::Lipshitz => NoiseShaping::Lipshitz,
+    };
+    let output = Output {
+        format,
+        bits_per_sample,
+        dither,
+        shaping,
+        info: Info {
+            title: cli.title.clone(),
+            artist: cli.artist.clone(),
+            comment: cli.comment.clone(),
+            creation_date: cli.date.clone(),
+            software: Some(format!("record-wav {}", env!("CARGO_PKG_VERSION"))),
+        },
+    };
+
+    let control = control()?;
+    if cli.pre_roll.is_none() {
+        control.arm();
     }
+    let mut segment = 1;
+    let mut recorded = 0.0;
+    let mut recorder = start_segment(
+        cli,
+        &output,
+        source.as_ref(),
+        segment,
+        cli.duration,
+        !control.is_armed(),
+    )?;
+
+    let result = loop {
+        let result = recorder.run(source.as_mut(), &control);
+        let device_name = match (&result, &device_name) {
+            (Err(RecordError::Source(SourceError::Disconnected)), Some(device_name))
+                if cli.reconnect != ReconnectPolicy::None =>
+            {
+                device_name
+            }
+            _ => break result.map_err(|err| err.to_string()),
+        };
+
+        // The source was only noticed gone a while after its last callback
+        let disconnected_at = source.last_capture().unwrap_or_else(Instant::now);
+        drop(source);
+        eprintln!("{}, reconnecting...", SourceError::Disconnected);
+        // Carrying on in the same files needs the same channels at the same rate
+        let expected = (!cli.segment_on_reconnect).then_some((channels, sample_rate));
+        source = match reconnect(cli, output_format, device_name, expected, &control) {
+            Ok(Some(source)) => source,
+            Ok(None) => break Ok(()),
+            Err(err) => break Err(err),
+        };
+
+        if cli.segment_on_reconnect {
+            recorded += finish_segment(cli, recorder, segment)?;
+            segment += 1;
+            let remaining = cli.duration.map(|duration| (duration - recorded).max(0.0));
+            recorder = start_segment(
+                cli,
+                &output,
+                source.as_ref(),
+                segment,
+                remaining,
+                !control.is_armed(),
+            )?;
+        } else {
+            let missing = if control.is_paused() {
+                0.0
+            } else {
+                disconnected_at.elapsed().as_secs_f64() * sample_rate as f64
+            };
+            if let Err(err) = recorder.insert_gap(missing.round() as u64, cli.fill_gaps) {
+                break Err(format!("failed to write samples: {}", err));
+            }
+            if recorder.is_complete() {
+                break Ok(());
+            }
+        }
+    };
 
-    fn push_sample(&mut self, sample: i16) {
-        self.samples.push(sample);
-    }
-}
+    // Whatever happened, what was captured is kept
+    finish_segment(cli, recorder, segment)?;
 
-trait BinarySerialize {
-    fn needed_size(&self) -> usize;
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()>;
+    result
 }
 
-impl BinarySerialize for WavFile {
-    fn needed_size(&self) -> usize {
-        44 + self.samples.needed_size()
+// Creates the files of a segment, `take.wav` then `take-2.wav`, `take-3.wav`... and a recorder
+// writing to them. On `standby` it only starts writing once armed, with the pre-roll
+fn start_segment(
+    cli: &RecordArgs,
+    output: &Output,
+    source: &dyn AudioSource,
+    segment: u32,
+    duration: Option<f64>,
+    standby: bool,
+) -> Result<Recorder<BufWriter<File>>, String> {
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+    let path = segment_path(&cli.output, segment);
+    // The ring buffer holds audio from when the stream started, before the files existed
+    let start_time = source.start_time().unwrap_or_else(SystemTime::now);
+    let bext = Bext {
+        description: cli.description.clone().unwrap_or_default(),
+        originator: "record-wav".to_string(),
+        ..Bext::originated_at(start_time, sample_rate)
+    };
+    let mut info = output.info.clone();
+    info.creation_date
+        .get_or_insert_with(|| bext.origination_date.clone());
+    let wav_file = |channels| {
+        let mut file = WavFile::new(output.format, channels, sample_rate, output.bits_per_sample);
+        file.info = info.clone();
+        file.bext = Some(bext.clone());
+        file
+    };
+
+    let (recorder, destination) = if cli.split {
+        let paths = (1..=channels)
+            .map(|channel| split_path(&path, channel))
+            .collect::<Vec<_>>();
+        let writers = paths
+            .iter()
+            .zip(0..)
+            .map(|(path, channel)| {
+                let dither = output
+                    .dither
+                    .then(|| Dither::new(1, output.shaping, cli.dither_seed.wrapping_add(channel)));
+                create_writer(path, wav_file(1), dither)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let destination = match paths.as_slice() {
+            [path] => path.display().to_string(),
+            [first, .., last] => format!("{} ... {}", first.display(), last.display()),
+            [] => unreachable!(),
+        };
+
+        (Recorder::split(writers), destination)
+    } else {
+        let dither = output
+            .dither
+            .then(|| Dither::new(channels, output.shaping, cli.dither_seed));
+        let writer = create_writer(&path, wav_file(channels), dither)?;
+
+        (Recorder::new(writer, channels), path.display().to_string())
+    };
+
+    let max_frames = duration.map(|seconds| (seconds * sample_rate as f64).round() as u64);
+    let meter =
+        (!cli.no_meter && std::io::stderr().is_terminal()).then(|| LevelMeter::new(channels));
+
+    let length = duration.map_or(String::new(), |seconds| format!("{} s ", seconds));
+    if standby {
+        println!(
+            "Standing by to record {}to {}, press Enter to start with the last {} s...",
+            length,
+            destination,
+            cli.pre_roll.unwrap_or_default()
+        );
+    } else {
+        println!("Recording {}to {}...", length, destination);
     }
+    println!("Press Enter or Ctrl+C to stop, m + Enter to add a marker, p + Enter to pause");
+
+    let pre_roll = cli
+        .pre_roll
+        .filter(|_| standby)
+        .map(|seconds| (seconds * sample_rate as f64).round() as u64);
+
+    Ok(recorder
+        .with_max_frames(max_frames)
+        .with_meter(meter)
+        .with_resume_cues(cli.cue_on_resume)
+        .with_pre_roll(pre_roll))
+}
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
-        }
-
-        buffer[0..4].copy_from_slice(b"RIFF");
-        let file_size = (self.needed_size() - 8) as u32;
-        buffer[4..8].copy_from_slice(&file_size.to_le_bytes());
-        buffer[8..12].copy_from_slice(b"WAVE");
-        buffer[12..16].copy_from_slice(b"fmt ");
-        // Hardcoded size, good enough here
-        16u32.serialize(&mut buffer[16..20])?;
-        self.format.serialize(&mut buffer[20..22])?;
-        self.channels.serialize(&mut buffer[22..24])?;
-        self.sample_rate.serialize(&mut buffer[24..28])?;
-
-        let avg_bytes_per_sec =
-            (self.sample_rate * self.bits_per_sample as u32 * self.channels as u32) / 8;
-        avg_bytes_per_sec.serialize(&mut buffer[28..32])?;
+// Finalizes the files and prints the report, returns the recorded duration not counting the
+// pre-roll
+fn finish_segment(
+    cli: &RecordArgs,
+    recorder: Recorder<BufWriter<File>>,
+    segment: u32,
+) -> Result<f64, String> {
+    let report = recorder.report();
+    let pre_roll = recorder.pre_roll_frames();
+    recorder
+        .finish()
+        .map_err(|err| format!("failed to finalize .wav file: {}", err))?;
+
+    summary::print_report(&report);
+    if let Some(path) = &cli.report {
+        summary::write_report(&report, &segment_path(path, segment))?;
+    }
 
-        let block_align = (self.bits_per_sample * self.channels) / 8;
-        block_align.serialize(&mut buffer[32..34])?;
+    Ok((report.frames - pre_roll) as f64 / report.sample_rate as f64)
+}
 
-        self.bits_per_sample.serialize(&mut buffer[34..36])?;
-        buffer[36..40].copy_from_slice(b"data");
+fn create_writer(
+    path: &Path,
+    file: WavFile,
+    dither: Option<Dither>,
+) -> Result<WavWriter<BufWriter<File>>, String> {
+    let output = File::create(path)
+        .map_err(|err| format!("failed to create {}: {}", path.display(), err))?;
+    let writer = WavWriter::new(BufWriter::new(output), file)
+        .map_err(|err| format!("failed to write .wav header: {}", err))?;
+
+    Ok(match dither {
+        Some(dither) => writer.with_dither(dither),
+        None => writer,
+    })
+}
 
-        let data_size = self.samples.needed_size() as u32;
-        buffer[40..44].copy_from_slice(&data_size.to_le_bytes());
+fn segment_path(path: &Path, segment: u32) -> PathBuf {
+    if segment == 1 {
+        return path.to_path_buf();
+    }
 
-        self.samples.serialize(&mut buffer[44..])?;
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or("wav".as_ref()).to_string_lossy();
 
-        Ok(())
-    }
+    path.with_file_name(format!("{}-{}.{}", stem, segment, extension))
 }
 
-#[repr(u16)]
-#[derive(Clone, Copy)]
-enum WavFormat {
-    PCM = 1,
+// `take.wav` becomes `take_1.wav`, `take_2.wav`...
+fn split_path(path: &Path, channel: u16) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or("wav".as_ref()).to_string_lossy();
+
+    path.with_file_name(format!("{}_{}.{}", stem, channel, extension))
 }
 
-impl BinarySerialize for WavFormat {
-    fn needed_size(&self) -> usize {
-        2
+// Also returns the name of the input device, to reconnect to it
+fn open_source(
+    cli: &RecordArgs,
+    output_format: Option<(WavFormat, u16)>,
+) -> Result<(Box<dyn AudioSource>, Option<String>), String> {
+    let channels = cli.channels.unwrap_or(GENERATOR_CHANNELS);
+    let sample_rate = cli
+        .device_rate
+        .or(cli.rate)
+        .unwrap_or(GENERATOR_SAMPLE_RATE);
+    if matches!(cli.source, SourceKind::Sine(_) | SourceKind::Noise) && cli.duration.is_none() {
+        return Err("generated sources need a --duration".to_string());
+    }
+    if cli.pre_roll.is_some() && !matches!(cli.source, SourceKind::Device) {
+        return Err("--pre-roll needs an input device".to_string());
     }
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
-        }
-
-        (*self as u16).serialize(buffer)?;
+    match &cli.source {
+        SourceKind::Device => {
+            let host = find_host(cli.host.as_deref())?;
+            let input_device = find_input_device(&host, cli.device.as_deref())?;
+            let source = start_device(cli, &input_device, output_format)?;
 
-        Ok(())
+            Ok((source, input_device.name().ok()))
+        }
+        SourceKind::Sine(frequency) => Ok((
+            Box::new(SineSource::new(
+                channels,
+                sample_rate,
+                *frequency,
+                GENERATOR_AMPLITUDE,
+            )),
+            None,
+        )),
+        SourceKind::Noise => Ok((
+            Box::new(NoiseSource::new(
+                channels,
+                sample_rate,
+                GENERATOR_AMPLITUDE,
+                0,
+            )),
+            None,
+        )),
+        SourceKind::File(path) => {
+            let source = FileSource::open(path)
+                .map_err(|err| format!("failed to open {}: {}", path.display(), err))?;
+            if let Some(channels) = cli.channels.filter(|&c| c != source.channels()) {
+                return Err(format!(
+                    "{} has {} channels, not {}",
+                    path.display(),
+                    source.channels(),
+                    channels
+                ));
+            }
+
+            Ok((Box::new(source), None))
+        }
     }
 }
 
-impl<T: BinarySerialize> BinarySerialize for Vec<T> {
-    fn needed_size(&self) -> usize {
-        if self.len() == 0 {
-            return 0;
-        }
+fn start_device(
+    cli: &RecordArgs,
+    device: &cpal::Device,
+    output_format: Option<(WavFormat, u16)>,
+) -> Result<Box<dyn AudioSource>, String> {
+    let config = choose_config(device, cli, output_format)?;
+    let source = CpalSource::new(device, &config, RING_BUFFER_SECONDS, cli.fill_gaps)
+        .map_err(|err| format!("failed to start recording: {}", err))?;
+
+    println!(
+        "Using input device: \"{}\"",
+        device.name().unwrap_or_default()
+    );
+
+    Ok(Box::new(source))
+}
 
-        self.len() * self[0].needed_size()
+// Channel mapping and resampling to `sample_rate`, applied to whatever the source delivers
+fn build_pipeline(
+    cli: &RecordArgs,
+    mut source: Box<dyn AudioSource>,
+    sample_rate: Option<u32>,
+) -> Result<Box<dyn AudioSource>, String> {
+    if cli.map.is_some() || cli.downmix.is_some() {
+        let selection = match &cli.map {
+            Some(map) => {
+                if let Some(&channel) = map.iter().find(|&&c| c > source.channels()) {
+                    return Err(format!(
+                        "cannot map channel {}, the source has {} channels",
+                        channel,
+                        source.channels()
+                    ));
+                }
+                Some(map.iter().map(|&c| c - 1).collect::<Vec<_>>())
+            }
+            None => None,
+        };
+        let map = ChannelMap::new(source.channels(), selection.as_deref(), cli.downmix);
+        source = Box::new(MappedSource::new(source, map));
     }
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
-        }
-
-        let mut off = 0;
-        for val in self {
-            val.serialize(&mut buffer[off..off + val.needed_size()])?;
-            off += val.needed_size();
-        }
-
-        Ok(())
+    if let Some(rate) = sample_rate.filter(|&rate| rate != source.sample_rate()) {
+        println!("Resampling from {} Hz to {} Hz", source.sample_rate(), rate);
+        let quality = match cli.resample_quality {
+            ResampleQuality::Low => Quality::Low,
+            ResampleQuality::Medium => Quality::Medium,
+            ResampleQuality::High => Quality::High,
+        };
+        source = Box::new(ResampledSource::new(source, rate, quality));
     }
-}
 
-impl BinarySerialize for u32 {
-    fn needed_size(&self) -> usize {
-        4
-    }
+    Ok(source)
+}
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
+// Tries to open the input device again until it works or the timeout expires. `None` if
+// recording was stopped meanwhile
+fn reconnect(
+    cli: &RecordArgs,
+    output_format: Option<(WavFormat, u16)>,
+    device_name: &str,
+    expected: Option<(u16, u32)>,
+    control: &Control,
+) -> Result<Option<Box<dyn AudioSource>>, String> {
+    let deadline = Instant::now() + Duration::from_secs_f64(cli.reconnect_timeout);
+    loop {
+        if control.is_stopped() {
+            return Ok(None);
         }
 
-        buffer[0..4].copy_from_slice(&self.to_le_bytes());
-
-        Ok(())
+        match try_reconnect(cli, output_format, device_name, expected) {
+            Ok(source) => return Ok(Some(source)),
+            Err(err) if Instant::now() >= deadline => {
+                return Err(format!("failed to reconnect: {}", err))
+            }
+            Err(_) => thread::sleep(RECONNECT_INTERVAL),
+        }
     }
 }
 
-impl BinarySerialize for u16 {
-    fn needed_size(&self) -> usize {
-        2
+fn try_reconnect(
+    cli: &RecordArgs,
+    output_format: Option<(WavFormat, u16)>,
+    device_name: &str,
+    expected: Option<(u16, u32)>,
+) -> Result<Box<dyn AudioSource>, String> {
+    let host = find_host(cli.host.as_deref())?;
+    let device = match find_input_device(&host, Some(device_name)) {
+        Err(_) if cli.reconnect == ReconnectPolicy::Default => find_input_device(&host, None),
+        result => result,
+    }?;
+
+    let source = start_device(cli, &device, output_format)?;
+    let sample_rate = expected.map(|(_, sample_rate)| sample_rate).or(cli.rate);
+    let source = build_pipeline(cli, source, sample_rate)?;
+    if let Some((channels, _)) = expected.filter(|&(channels, _)| channels != source.channels()) {
+        return Err(format!(
+            "the device now has {} channels instead of {}",
+            source.channels(),
+            channels
+        ));
     }
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
-        }
-
-        buffer[0..2].copy_from_slice(&self.to_le_bytes());
-
-        Ok(())
-    }
+    Ok(source)
 }
 
-impl BinarySerialize for i16 {
-    fn needed_size(&self) -> usize {
-        2
-    }
+fn find_host(name: Option<&str>) -> Result<cpal::Host, String> {
+    let name = match name {
+        Some(name) => name,
+        None => return Ok(cpal::default_host()),
+    };
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
-        }
+    let host_id = cpal::available_hosts()
+        .into_iter()
+        .find(|host_id| host_id.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("no audio host named \"{}\"", name))?;
 
-        buffer[0..2].copy_from_slice(&self.to_le_bytes());
+    cpal::host_from_id(host_id).map_err(|err| format!("failed to open host {}: {}", name, err))
+}
 
-        Ok(())
-    }
+fn find_input_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::Device, String> {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            return host
+                .default_input_device()
+                .ok_or_else(|| "no input device available".to_string())
+        }
+    };
+
+    let devices = host
+        .input_devices()
+        .map_err(|err| format!("failed to list input devices: {}", err))?
+        .filter_map(|device| Some((device.name().ok()?, device)))
+        .collect::<Vec<_>>();
+
+    // An exact match wins over a device whose name merely contains the requested one
+    let position = devices
+        .iter()
+        .position(|(device_name, _)| device_name == name)
+        .or_else(|| {
+            devices
+                .iter()
+                .position(|(device_name, _)| device_name.contains(name))
+        })
+        .ok_or_else(|| format!("no input device named \"{}\"", name))?;
+
+    Ok(devices.into_iter().nth(position).unwrap().1)
 }
 
-impl BinarySerialize for u8 {
-    fn needed_size(&self) -> usize {
-        1
+// `None` keeps the native sample format of the device
+fn output_sample_format(cli: &RecordArgs) -> Result<Option<(WavFormat, u16)>, String> {
+    match (cli.format, cli.bits) {
+        (None, None) => Ok(None),
+        (Some(OutputFormat::Float), None | Some(32)) => Ok(Some((WavFormat::IeeeFloat, 32))),
+        (Some(OutputFormat::Float), Some(_)) => {
+            Err("float output only supports 32 bits per sample".to_string())
+        }
+        (_, bits) => Ok(Some((WavFormat::Pcm, bits.unwrap_or(16)))),
     }
+}
 
-    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
-        if buffer.len() < self.needed_size() {
-            return Err(());
+fn choose_config(
+    device: &cpal::Device,
+    cli: &RecordArgs,
+    output_format: Option<(WavFormat, u16)>,
+) -> Result<cpal::SupportedStreamConfig, String> {
+    let supported_configs = device
+        .supported_input_configs()
+        .map_err(|err| format!("error while querying configs: {}", err))?
+        .collect::<Vec<_>>();
+
+    // Capture floats when the output has more resolution than 16 bits, 16-bit integers otherwise.
+    // Whatever the device offers is converted anyway.
+    let formats = match output_format {
+        Some((WavFormat::IeeeFloat, _)) | Some((_, 17..)) => {
+            [SampleFormat::F32, SampleFormat::I16, SampleFormat::U16]
         }
+        _ => [SampleFormat::I16, SampleFormat::U16, SampleFormat::F32],
+    };
+    // Enough channels for the highest mapped one
+    let min_channels = cli
+        .map
+        .as_ref()
+        .and_then(|map| map.iter().max().copied())
+        .unwrap_or(1);
+    let find_range = |rate: Option<u32>| {
+        formats.iter().find_map(|&format| {
+            supported_configs.iter().find(|supported_range| {
+                supported_range.sample_format() == format
+                    && match cli.channels {
+                        Some(channels) => supported_range.channels() == channels,
+                        None => supported_range.channels() >= min_channels,
+                    }
+                    && rate.is_none_or(|rate| {
+                        supported_range.min_sample_rate().0 <= rate
+                            && rate <= supported_range.max_sample_rate().0
+                    })
+            })
+        })
+    };
+
+    // Capturing at the output rate avoids resampling, but any rate will do unless one was asked
+    let (supported_range, rate) = match (cli.device_rate, cli.rate) {
+        (Some(rate), _) => (find_range(Some(rate)), Some(rate)),
+        (None, Some(rate)) => match find_range(Some(rate)) {
+            Some(range) => (Some(range), Some(rate)),
+            None => (find_range(None), None),
+        },
+        (None, None) => (find_range(None), None),
+    };
+    let supported_range = supported_range
+        .ok_or_else(|| "no supported config matches the requested channels and rate".to_string())?
+        .clone();
+
+    Ok(match rate {
+        Some(rate) => supported_range.with_sample_rate(cpal::SampleRate(rate)),
+        None => supported_range.with_max_sample_rate(),
+    })
+}
 
-        buffer[0] = *self;
+// Driven by SIGINT/SIGTERM, which stop so the files are always finalized, and by stdin: Enter
+// arms a recorder standing by then stops, `m` adds a marker and `p` pauses or resumes
+fn control() -> Result<Arc<Control>, String> {
+    let control = Arc::new(Control::new());
+
+    let signal_control = control.clone();
+    ctrlc::set_handler(move || signal_control.stop())
+        .map_err(|err| format!("failed to set the signal handler: {}", err))?;
+
+    let stdin_control = control.clone();
+    thread::spawn(move || {
+        // Stdin is at EOF right away when running in the background, only a line stops
+        for line in std::io::stdin().lines().map_while(Result::ok) {
+            match line.trim().split_once(' ').unwrap_or((line.trim(), "")) {
+                ("", _) if !stdin_control.is_armed() => stdin_control.arm(),
+                ("", _) => {
+                    stdin_control.stop();
+                    break;
+                }
+                ("m", note) => {
+                    let note = note.trim();
+                    stdin_control.add_marker((!note.is_empty()).then(|| note.to_string()));
+                }
+                ("p", _) if stdin_control.is_paused() => stdin_control.resume(),
+                ("p", _) => stdin_control.pause(),
+                _ => eprintln!(
+                    "unknown command, press Enter to stop, type m [NOTE] to add a marker or p to \
+                     pause and resume"
+                ),
+            }
+        }
+    });
 
-        Ok(())
-    }
+    Ok(control)
 }
@@ -0,0 +1,153 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::report::CLIP_LEVEL;
+
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+const HOLD_TIME: Duration = Duration::from_secs(2);
+// Range of the bar graph
+const FLOOR_DB: f32 = -60.0;
+const BAR_WIDTH: usize = 40;
+
+struct Channel {
+    peak: f32,
+    squares: f64,
+    hold: f32,
+    hold_since: Instant,
+    clips: u64,
+}
+
+/// Peak and RMS meters of every channel drawn on the terminal, with peak hold and clip counts.
+pub struct LevelMeter {
+    channels: Vec<Channel>,
+    frames: u64,
+    last_update: Instant,
+    // Lines drawn last time, to go back up and redraw them
+    lines: usize,
+}
+
+impl LevelMeter {
+    pub fn new(channels: u16) -> Self {
+        let now = Instant::now();
+        let channels = (0..channels)
+            .map(|_| Channel {
+                peak: 0.0,
+                squares: 0.0,
+                hold: 0.0,
+                hold_since: now,
+                clips: 0,
+            })
+            .collect();
+
+        Self {
+            channels,
+            frames: 0,
+            last_update: now,
+            lines: 0,
+        }
+    }
+
+    /// Measures interleaved samples and redraws the meters if it is time to.
+    pub fn process(&mut self, samples: &[f32]) {
+        let count = self.channels.len();
+        for frame in samples.chunks_exact(count) {
+            for (channel, &sample) in self.channels.iter_mut().zip(frame) {
+                let level = sample.abs();
+                channel.peak = channel.peak.max(level);
+                channel.squares += (sample as f64) * (sample as f64);
+                if level >= CLIP_LEVEL {
+                    channel.clips += 1;
+                }
+            }
+        }
+        self.frames += (samples.len() / count) as u64;
+
+        let now = Instant::now();
+        if now - self.last_update >= UPDATE_INTERVAL {
+            self.last_update = now;
+            // The terminal going away is no reason to stop recording
+            let _ = self.draw(now);
+        }
+    }
+
+    /// Starts drawing below whatever was printed since the last update instead of over it.
+    pub fn interrupt(&mut self) {
+        self.lines = 0;
+    }
+
+    fn draw(&mut self, now: Instant) -> io::Result<()> {
+        let mut out = String::new();
+        if self.lines > 0 {
+            out += &format!("\x1b[{}A", self.lines);
+        }
+
+        for (i, channel) in self.channels.iter_mut().enumerate() {
+            let rms = (channel.squares / self.frames.max(1) as f64).sqrt() as f32;
+            if channel.peak >= channel.hold || now - channel.hold_since >= HOLD_TIME {
+                channel.hold = channel.peak;
+                channel.hold_since = now;
+            }
+
+            let clip = match channel.clips {
+                0 => String::new(),
+                clips => format!("  CLIP x{}", clips),
+            };
+            out += &format!(
+                "\r\x1b[K{:>2} {} {:>6} peak {:>6} rms {:>6} hold{}\n",
+                i + 1,
+                bar(channel.peak, rms, channel.hold),
+                format_db(channel.peak),
+                format_db(rms),
+                format_db(channel.hold),
+                clip
+            );
+
+            channel.peak = 0.0;
+            channel.squares = 0.0;
+        }
+        self.frames = 0;
+        self.lines = self.channels.len();
+
+        let mut stderr = io::stderr().lock();
+        stderr.write_all(out.as_bytes())?;
+        stderr.flush()
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    20.0 * level.log10()
+}
+
+fn format_db(level: f32) -> String {
+    match to_db(level) {
+        db if db < FLOOR_DB => "-inf".to_string(),
+        db => format!("{:.1}", db),
+    }
+}
+
+// `#` up to the RMS level, `=` up to the peak and `|` at the held peak
+fn bar(peak: f32, rms: f32, hold: f32) -> String {
+    let position = |level: f32| {
+        let fraction = (to_db(level) - FLOOR_DB) / -FLOOR_DB;
+        (fraction.clamp(0.0, 1.0) * BAR_WIDTH as f32).round() as usize
+    };
+    let (rms, peak, hold) = (position(rms), position(peak), position(hold));
+
+    let mut bar = String::with_capacity(BAR_WIDTH + 2);
+    bar.push('[');
+    for i in 0..BAR_WIDTH {
+        bar.push(if hold > 0 && i + 1 == hold {
+            '|'
+        } else if i < rms {
+            '#'
+        } else if i < peak {
+            '='
+        } else {
+            ' '
+        });
+    }
+    bar.push(']');
+    bar
+}
@@ -0,0 +1,296 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    marker::PhantomData,
+    path::Path,
+};
+
+use crate::{
+    bext::Bext,
+    info::Info,
+    sample::Sample,
+    serialize::BinaryDeserialize,
+    wav::{default_channel_mask, WavFile, WavFormat, SUBFORMAT_GUID_TAIL, WAVE_FORMAT_EXTENSIBLE},
+};
+
+#[derive(Debug)]
+pub enum WavError {
+    Io(io::Error),
+    NotRiff,
+    NotWave,
+    Truncated,
+    MissingFmt,
+    MissingData,
+    InvalidDs64,
+    InvalidFmt(&'static str),
+    UnsupportedFormat(u16),
+    UnsupportedBitDepth(u16),
+    SampleTypeMismatch {
+        format: WavFormat,
+        bits_per_sample: u16,
+    },
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::Io(err) => write!(f, "i/o error: {}", err),
+            WavError::NotRiff => write!(f, "not a RIFF file"),
+            WavError::NotWave => write!(f, "RIFF file is not of type WAVE"),
+            WavError::Truncated => write!(f, "file is truncated"),
+            WavError::MissingFmt => write!(f, "no `fmt ` chunk"),
+            WavError::MissingData => write!(f, "no `data` chunk"),
+            WavError::InvalidDs64 => write!(f, "RF64 file without a valid `ds64` chunk"),
+            WavError::InvalidFmt(reason) => write!(f, "invalid `fmt ` chunk: {}", reason),
+            WavError::UnsupportedFormat(tag) => write!(f, "unsupported format tag {:#06x}", tag),
+            WavError::UnsupportedBitDepth(bits) => {
+                write!(f, "unsupported sample size of {} bits", bits)
+            }
+            WavError::SampleTypeMismatch {
+                format,
+                bits_per_sample,
+            } => write!(
+                f,
+                "samples are stored as {:?} with {} bits",
+                format, bits_per_sample
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WavError {}
+
+impl From<io::Error> for WavError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            WavError::Truncated
+        } else {
+            WavError::Io(err)
+        }
+    }
+}
+
+/// Parses the chunks of a .wav file up front, then streams the samples of its `data` chunk.
+pub struct WavReader<R: Read + Seek> {
+    inner: R,
+    file: WavFile,
+    data_remaining: u64,
+}
+
+impl WavReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WavError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> WavReader<R> {
+    pub fn new(mut inner: R) -> Result<Self, WavError> {
+        let stream_len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+
+        let mut riff_header = [0u8; 12];
+        inner.read_exact(&mut riff_header)?;
+        let rf64 = match &riff_header[0..4] {
+            b"RIFF" => false,
+            b"RF64" | b"BW64" => true,
+            _ => return Err(WavError::NotRiff),
+        };
+        if &riff_header[8..12] != b"WAVE" {
+            return Err(WavError::NotWave);
+        }
+
+        // Don't trust the RIFF size past the end of the stream. The real size of RF64 files is
+        // in their `ds64` chunk
+        let riff_size = u32::deserialize(&riff_header[4..8]).unwrap() as u64;
+        let mut riff_end = if rf64 {
+            stream_len
+        } else {
+            (8 + riff_size).min(stream_len)
+        };
+        let mut ds64_data_size = None;
+
+        let mut file = None;
+        let mut data = None;
+        let mut info = Info::default();
+        let mut bext = None;
+
+        let mut pos = 12;
+        while pos + 8 <= riff_end {
+            let mut chunk_header = [0u8; 8];
+            inner.read_exact(&mut chunk_header)?;
+            let mut size = u32::deserialize(&chunk_header[4..8]).unwrap() as u64;
+            if let (b"data", Some(ds64_size)) = (&chunk_header[0..4], ds64_data_size) {
+                if size == u32::MAX as u64 {
+                    size = ds64_size;
+                }
+            }
+            let body_start = pos + 8;
+
+            match &chunk_header[0..4] {
+                b"ds64" if rf64 => {
+                    if size < 24 || body_start + size > riff_end {
+                        return Err(WavError::InvalidDs64);
+                    }
+                    let mut body = [0u8; 24];
+                    inner.read_exact(&mut body)?;
+                    let riff_size = u64::deserialize(&body[0..8]).unwrap();
+                    riff_end = riff_size.saturating_add(8).min(stream_len);
+                    ds64_data_size = Some(u64::deserialize(&body[8..16]).unwrap());
+                }
+                b"fmt " => {
+                    if body_start + size > riff_end {
+                        return Err(WavError::Truncated);
+                    }
+                    let mut body = vec![0u8; size as usize];
+                    inner.read_exact(&mut body)?;
+                    file = Some(parse_fmt(&body)?);
+                }
+                b"bext" if body_start + size <= riff_end => {
+                    let mut body = vec![0u8; size as usize];
+                    inner.read_exact(&mut body)?;
+                    bext = Bext::parse(&body);
+                }
+                // Metadata isn't worth failing for, a truncated list is ignored
+                b"LIST" if size >= 4 && body_start + size <= riff_end => {
+                    let mut body = vec![0u8; size as usize];
+                    inner.read_exact(&mut body)?;
+                    if &body[0..4] == b"INFO" {
+                        info = Info::parse(&body[4..]);
+                    }
+                }
+                b"data" => {
+                    let available = riff_end - body_start;
+                    data = Some((body_start, size.min(available)));
+                }
+                _ => {}
+            }
+
+            // Chunks are word aligned, odd sized ones are followed by a pad byte
+            pos = body_start.saturating_add(size).saturating_add(size & 1);
+            inner.seek(SeekFrom::Start(pos))?;
+        }
+
+        if rf64 && ds64_data_size.is_none() {
+            return Err(WavError::InvalidDs64);
+        }
+
+        let mut file = file.ok_or(WavError::MissingFmt)?;
+        let (data_start, data_size) = data.ok_or(WavError::MissingData)?;
+        file.data_size = data_size;
+        file.reserve_ds64 = rf64;
+        file.info = info;
+        file.bext = bext;
+        inner.seek(SeekFrom::Start(data_start))?;
+
+        Ok(Self {
+            inner,
+            file,
+            data_remaining: data_size,
+        })
+    }
+
+    pub fn file(&self) -> &WavFile {
+        &self.file
+    }
+
+    pub fn samples<S: Sample>(&mut self) -> Result<Samples<'_, R, S>, WavError> {
+        if self.file.format != S::FORMAT || self.file.bits_per_sample != S::BITS_PER_SAMPLE {
+            return Err(WavError::SampleTypeMismatch {
+                format: self.file.format,
+                bits_per_sample: self.file.bits_per_sample,
+            });
+        }
+
+        Ok(Samples {
+            reader: self,
+            sample_type: PhantomData,
+        })
+    }
+
+    pub fn read_samples<S: Sample>(&mut self) -> Result<Vec<S>, WavError> {
+        self.samples()?.collect()
+    }
+}
+
+fn parse_fmt(body: &[u8]) -> Result<WavFile, WavError> {
+    if body.len() < 16 {
+        return Err(WavError::InvalidFmt("chunk is too small"));
+    }
+
+    let channels = u16::deserialize(&body[2..4]).unwrap();
+    let mut tag = u16::deserialize(&body[0..2]).unwrap();
+    let mut channel_mask = default_channel_mask(channels);
+    if tag == WAVE_FORMAT_EXTENSIBLE {
+        if body.len() < 40 {
+            return Err(WavError::InvalidFmt("extensible chunk is too small"));
+        }
+        if body[26..40] != SUBFORMAT_GUID_TAIL {
+            return Err(WavError::InvalidFmt("unknown subformat GUID"));
+        }
+        channel_mask = u32::deserialize(&body[20..24]).unwrap();
+        tag = u16::deserialize(&body[24..26]).unwrap();
+    }
+    let format =
+        WavFormat::deserialize(&tag.to_le_bytes()).map_err(|_| WavError::UnsupportedFormat(tag))?;
+
+    let file = WavFile {
+        format,
+        channels,
+        sample_rate: u32::deserialize(&body[4..8]).unwrap(),
+        bits_per_sample: u16::deserialize(&body[14..16]).unwrap(),
+        channel_mask,
+        reserve_ds64: false,
+        data_size: 0,
+        trailer_size: 0,
+        info: Info::default(),
+        bext: None,
+    };
+    let block_align = u16::deserialize(&body[12..14]).unwrap();
+
+    if file.channels == 0 {
+        return Err(WavError::InvalidFmt("no channels"));
+    }
+    if file.sample_rate == 0 {
+        return Err(WavError::InvalidFmt("sample rate is zero"));
+    }
+    if file.bits_per_sample == 0 || !file.bits_per_sample.is_multiple_of(8) {
+        return Err(WavError::UnsupportedBitDepth(file.bits_per_sample));
+    }
+    match file.frame_size() {
+        None | Some(0) => return Err(WavError::InvalidFmt("frame size is out of range")),
+        Some(frame_size) if frame_size != block_align => {
+            return Err(WavError::InvalidFmt(
+                "block align does not match the sample size",
+            ))
+        }
+        Some(_) => {}
+    }
+
+    Ok(file)
+}
+
+pub struct Samples<'a, R: Read + Seek, S: Sample> {
+    reader: &'a mut WavReader<R>,
+    sample_type: PhantomData<S>,
+}
+
+impl<'a, R: Read + Seek, S: Sample> Iterator for Samples<'a, R, S> {
+    type Item = Result<S, WavError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = S::BITS_PER_SAMPLE as usize / 8;
+        if self.reader.data_remaining < size as u64 {
+            return None;
+        }
+
+        let mut bytes = [0u8; 8];
+        if let Err(err) = self.reader.inner.read_exact(&mut bytes[..size]) {
+            self.reader.data_remaining = 0;
+            return Some(Err(err.into()));
+        }
+        self.reader.data_remaining -= size as u64;
+
+        Some(Ok(S::deserialize(&bytes[..size]).unwrap()))
+    }
+}
@@ -0,0 +1,458 @@
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    io::{Seek, Write},
+    mem, thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    control::Control,
+    meter::LevelMeter,
+    report::{Report, Statistics},
+    source::{AudioSource, Gap, SourceError},
+    writer::WavWriter,
+};
+
+const BLOCK_FRAMES: usize = 1024;
+// How long to wait for a live source that has nothing buffered yet
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Debug)]
+pub enum RecordError {
+    Source(SourceError),
+    Io(io::Error),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Source(err) => write!(f, "{}", err),
+            RecordError::Io(err) => write!(f, "failed to write samples: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<SourceError> for RecordError {
+    fn from(err: SourceError) -> Self {
+        RecordError::Source(err)
+    }
+}
+
+impl From<io::Error> for RecordError {
+    fn from(err: io::Error) -> Self {
+        RecordError::Io(err)
+    }
+}
+
+/// Moves samples from an `AudioSource` to a `WavWriter`, or to one mono `WavWriter` per channel.
+pub struct Recorder<W: Write + Seek> {
+    writers: Vec<WavWriter<W>>,
+    channels: usize,
+    split: bool,
+    max_frames: Option<u64>,
+    meter: Option<LevelMeter>,
+    statistics: Statistics,
+    frames: u64,
+    // Frames recorded before the current source started, its gap positions are relative to it
+    source_start: u64,
+    // Frames of the current source thrown away while standing by or paused
+    skipped: u64,
+    // The last samples while standing by, until armed
+    pre_roll: Option<VecDeque<f32>>,
+    pre_roll_len: usize,
+    // Frames of pre-roll the recording started with
+    pre_rolled: u64,
+    // Markers asked for while standing by, at their frame of the current source
+    pending_markers: Vec<(u64, Option<String>)>,
+    paused: bool,
+    resume_cues: bool,
+    resumes: u32,
+    dropped: u64,
+    gaps: Vec<Gap>,
+    errors: Vec<String>,
+    markers: u32,
+    block: Vec<f32>,
+    // One channel of `block`, when split
+    channel_block: Vec<f32>,
+}
+
+impl<W: Write + Seek> Recorder<W> {
+    pub fn new(writer: WavWriter<W>, channels: u16) -> Self {
+        Self::with_writers(vec![writer], channels, false)
+    }
+
+    /// Writes each channel of the source to its own writer, in order. All the files start and
+    /// end on the same frame.
+    pub fn split(writers: Vec<WavWriter<W>>) -> Self {
+        let channels = writers.len() as u16;
+        Self::with_writers(writers, channels, true)
+    }
+
+    fn with_writers(writers: Vec<WavWriter<W>>, channels: u16, split: bool) -> Self {
+        Self {
+            writers,
+            channels: channels as usize,
+            split,
+            max_frames: None,
+            meter: None,
+            statistics: Statistics::new(channels),
+            frames: 0,
+            source_start: 0,
+            skipped: 0,
+            pre_roll: None,
+            pre_roll_len: 0,
+            pre_rolled: 0,
+            pending_markers: Vec::new(),
+            paused: false,
+            resume_cues: false,
+            resumes: 0,
+            dropped: 0,
+            gaps: Vec::new(),
+            errors: Vec::new(),
+            markers: 0,
+            block: vec![0.0; BLOCK_FRAMES * channels as usize],
+            channel_block: Vec::with_capacity(if split { BLOCK_FRAMES } else { 0 }),
+        }
+    }
+
+    /// Stops recording after this many frames.
+    pub fn with_max_frames(mut self, max_frames: Option<u64>) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Shows the levels of what is recorded.
+    pub fn with_meter(mut self, meter: Option<LevelMeter>) -> Self {
+        self.meter = meter;
+        self
+    }
+
+    /// Marks every point where recording resumed after a pause with a cue.
+    pub fn with_resume_cues(mut self, resume_cues: bool) -> Self {
+        self.resume_cues = resume_cues;
+        self
+    }
+
+    /// Stands by until the `Control` is armed, then starts with up to this many frames captured
+    /// before. They don't count in the maximum length.
+    pub fn with_pre_roll(mut self, frames: Option<u64>) -> Self {
+        self.pre_roll_len = frames.unwrap_or(0) as usize * self.channels;
+        self.pre_roll = frames.map(|_| VecDeque::with_capacity(self.pre_roll_len));
+        self
+    }
+
+    /// Sets when the first sample was captured, in the `bext` chunk of the files that have one.
+    pub fn set_origination(&mut self, time: SystemTime) {
+        for writer in &mut self.writers {
+            writer.set_origination(time);
+        }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Frames captured before the recording was armed, at the start of `frames`.
+    pub fn pre_roll_frames(&self) -> u64 {
+        self.pre_rolled
+    }
+
+    /// Samples the source lost because they were not read in time.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Levels, clipping and dropouts of what was recorded so far.
+    pub fn report(&self) -> Report {
+        let sample_rate = self.writers[0].file().sample_rate;
+
+        Report {
+            sample_rate,
+            frames: self.frames,
+            dropped: self.dropped,
+            channels: self.statistics.channel_reports(sample_rate),
+            gaps: self.gaps.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+
+    /// Records until the source is exhausted, the maximum length is reached, or `control` is
+    /// stopped. The source is still read while `control` is paused, but its samples are thrown
+    /// away. Can be called again with another source to carry on in the same files.
+    pub fn run(
+        &mut self,
+        source: &mut dyn AudioSource,
+        control: &Control,
+    ) -> Result<(), RecordError> {
+        self.source_start = self.frames;
+        self.skipped = 0;
+        // The audio and markers of another source don't line up with this one
+        if let Some(pre_roll) = &mut self.pre_roll {
+            pre_roll.clear();
+            self.pending_markers.clear();
+        }
+        let mut stopping = false;
+        loop {
+            if self.pre_roll.is_some() && control.is_armed() {
+                self.arm()?;
+            }
+            self.set_paused(control.is_paused());
+            for note in control.take_markers() {
+                self.add_marker(note);
+            }
+            if !stopping && control.is_stopped() {
+                // Live sources are drained of what they already captured
+                if !source.is_live() {
+                    break;
+                }
+                source.stop();
+                stopping = true;
+            }
+
+            self.collect_problems(source);
+
+            let len = match source.read(&mut self.block)? {
+                Some(0) => {
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Some(len) => len,
+                None => break,
+            };
+
+            if let Some(pre_roll) = &mut self.pre_roll {
+                pre_roll.extend(&self.block[..len]);
+                let excess = pre_roll.len().saturating_sub(self.pre_roll_len);
+                pre_roll.drain(..excess);
+            }
+            if self.pre_roll.is_some() || self.paused {
+                self.skipped += (len / self.channels) as u64;
+                if let Some(meter) = &mut self.meter {
+                    meter.process(&self.block[..len]);
+                }
+                continue;
+            }
+
+            let mut frames = (len / self.channels) as u64;
+            if let Some(max_frames) = self.max_frames {
+                frames = frames.min(max_frames - self.frames);
+            }
+            let len = frames as usize * self.channels;
+
+            self.statistics.process(&self.block[..len]);
+            if let Some(meter) = &mut self.meter {
+                meter.process(&self.block[..len]);
+            }
+            self.write(len)?;
+            self.frames += frames;
+
+            if self.is_complete() {
+                source.stop();
+                break;
+            }
+        }
+        self.collect_problems(source);
+
+        Ok(())
+    }
+
+    fn collect_problems(&mut self, source: &mut dyn AudioSource) {
+        let dropped = source.take_dropped();
+        if dropped > 0 {
+            eprintln!("input overflow, {} samples dropped", dropped);
+            if let Some(meter) = &mut self.meter {
+                meter.interrupt();
+            }
+            self.dropped += dropped;
+        }
+
+        let gaps = source.take_gaps();
+        // What is lost before recording or while paused wouldn't have been recorded anyway
+        if self.pre_roll.is_none() && !self.paused {
+            for gap in gaps {
+                self.record_gap(Gap {
+                    frame: (self.source_start + gap.frame).saturating_sub(self.skipped),
+                    ..gap
+                });
+            }
+        }
+
+        self.errors.extend(source.take_errors());
+    }
+
+    /// Whether the maximum length was reached.
+    pub fn is_complete(&self) -> bool {
+        self.max_frames == Some(self.frames)
+    }
+
+    /// Records a gap of `frames` at the current position, e.g. while there was no source, filled
+    /// with silence if `fill` is set. Nothing is recorded while standing by.
+    pub fn insert_gap(&mut self, frames: u64, fill: bool) -> io::Result<()> {
+        if frames == 0 || self.pre_roll.is_some() {
+            return Ok(());
+        }
+        self.record_gap(Gap {
+            frame: self.frames,
+            frames,
+            filled: fill,
+        });
+        if !fill {
+            return Ok(());
+        }
+
+        let mut remaining = match self.max_frames {
+            Some(max_frames) => frames.min(max_frames - self.frames),
+            None => frames,
+        };
+        self.block.fill(0.0);
+        while remaining > 0 {
+            let frames = remaining.min(BLOCK_FRAMES as u64);
+            let len = frames as usize * self.channels;
+            self.statistics.process(&self.block[..len]);
+            self.write(len)?;
+            self.frames += frames;
+            remaining -= frames;
+        }
+
+        Ok(())
+    }
+
+    // Writes the pre-roll and starts recording
+    fn arm(&mut self) -> io::Result<()> {
+        let mut pre_roll = match self.pre_roll.take() {
+            Some(pre_roll) => pre_roll,
+            None => return Ok(()),
+        };
+        let frames = (pre_roll.len() / self.channels) as u64;
+        self.pre_rolled = frames;
+        self.skipped -= frames;
+        if let Some(max_frames) = &mut self.max_frames {
+            *max_frames += frames;
+        }
+
+        // Markers from before the pre-roll have nothing left to point at
+        for (frame, note) in mem::take(&mut self.pending_markers) {
+            match frame.checked_sub(self.skipped) {
+                Some(offset) => self.place_marker(self.frames + offset, note),
+                None => eprintln!("marker dropped, it is older than the pre-roll"),
+            }
+        }
+
+        let sample_rate = self.writers[0].file().sample_rate;
+        let seconds = frames as f64 / sample_rate as f64;
+        eprintln!("recording, with {:.3} s of pre-roll", seconds);
+        if let Some(meter) = &mut self.meter {
+            meter.interrupt();
+        }
+        self.set_origination(SystemTime::now() - Duration::from_secs_f64(seconds));
+
+        for chunk in pre_roll.make_contiguous().chunks(self.block.len()) {
+            self.block[..chunk.len()].copy_from_slice(chunk);
+            self.statistics.process(chunk);
+            self.write(chunk.len())?;
+            self.frames += (chunk.len() / self.channels) as u64;
+        }
+
+        Ok(())
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        if paused == self.paused {
+            return;
+        }
+        self.paused = paused;
+
+        let sample_rate = self.writers[0].file().sample_rate;
+        let time = self.frames as f64 / sample_rate as f64;
+        if paused {
+            eprintln!("paused at {:.3} s", time);
+        } else {
+            eprintln!("resumed at {:.3} s", time);
+            self.resumes += 1;
+            // Nothing is recorded yet while standing by
+            if self.resume_cues && self.pre_roll.is_none() {
+                for writer in &mut self.writers {
+                    writer.add_cue(self.frames, format!("resume {}", self.resumes), None);
+                }
+            }
+        }
+        if let Some(meter) = &mut self.meter {
+            meter.interrupt();
+        }
+    }
+
+    /// Adds a numbered marker at the current position. While standing by, it is kept until
+    /// armed and dropped if it is older than the pre-roll.
+    pub fn add_marker(&mut self, note: Option<String>) {
+        if self.pre_roll.is_some() {
+            self.pending_markers.push((self.skipped, note));
+        } else {
+            self.place_marker(self.frames, note);
+        }
+    }
+
+    fn place_marker(&mut self, frame: u64, note: Option<String>) {
+        self.markers += 1;
+        let label = format!("marker {}", self.markers);
+        let sample_rate = self.writers[0].file().sample_rate;
+        eprintln!("{} at {:.3} s", label, frame as f64 / sample_rate as f64);
+        if let Some(meter) = &mut self.meter {
+            meter.interrupt();
+        }
+
+        for writer in &mut self.writers {
+            writer.add_cue(frame, label.clone(), note.clone());
+        }
+    }
+
+    fn record_gap(&mut self, gap: Gap) {
+        let sample_rate = self.writers[0].file().sample_rate;
+        let time = gap.frame as f64 / sample_rate as f64;
+        let length = gap.frames as f64 * 1000.0 / sample_rate as f64;
+        eprintln!(
+            "gap of {:.1} ms in the input at {:.3} s{}",
+            length,
+            time,
+            if gap.filled {
+                ", filled with silence"
+            } else {
+                ""
+            }
+        );
+        if let Some(meter) = &mut self.meter {
+            meter.interrupt();
+        }
+
+        for writer in &mut self.writers {
+            writer.add_cue(gap.frame, format!("gap {:.1} ms", length), None);
+        }
+        self.gaps.push(gap);
+    }
+
+    fn write(&mut self, len: usize) -> io::Result<()> {
+        let samples = &self.block[..len];
+        if !self.split {
+            return self.writers[0].write_samples(samples);
+        }
+
+        for (channel, writer) in self.writers.iter_mut().enumerate() {
+            self.channel_block.clear();
+            self.channel_block
+                .extend(samples.iter().skip(channel).step_by(self.channels));
+            writer.write_samples(&self.channel_block)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finalizes every file, in channel order when split.
+    pub fn finish(self) -> io::Result<Vec<W>> {
+        self.writers
+            .into_iter()
+            .map(|writer| writer.finalize())
+            .collect()
+    }
+}
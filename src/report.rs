@@ -0,0 +1,103 @@
+use crate::source::Gap;
+
+// Samples this close to full scale count as clipped, integer samples never quite reach 1.0
+pub const CLIP_LEVEL: f32 = 0.999;
+// Only the start of the first clips is kept, a badly clipped take would list millions
+const MAX_CLIP_TIMES: usize = 100;
+
+/// What happened during a recording, to tell whether the take is usable.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub sample_rate: u32,
+    pub frames: u64,
+    /// Samples lost because they were not read in time.
+    pub dropped: u64,
+    pub channels: Vec<ChannelReport>,
+    /// Discontinuities detected in the source, positions are in frames of the recording.
+    pub gaps: Vec<Gap>,
+    /// Errors reported by the audio stream.
+    pub errors: Vec<String>,
+}
+
+impl Report {
+    /// In seconds.
+    pub fn duration(&self) -> f64 {
+        self.frames as f64 / self.sample_rate as f64
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChannelReport {
+    pub peak: f32,
+    pub rms: f32,
+    pub dc_offset: f32,
+    pub clipped_samples: u64,
+    /// Start of every run of clipped samples in seconds, only the first ones are kept.
+    pub clip_times: Vec<f64>,
+}
+
+#[derive(Default)]
+struct ChannelStatistics {
+    peak: f32,
+    sum: f64,
+    squares: f64,
+    clipped_samples: u64,
+    clipping: bool,
+    clip_frames: Vec<u64>,
+}
+
+/// Accumulates the statistics of a recording block after block.
+pub struct Statistics {
+    channels: Vec<ChannelStatistics>,
+    frames: u64,
+}
+
+impl Statistics {
+    pub fn new(channels: u16) -> Self {
+        Self {
+            channels: (0..channels).map(|_| Default::default()).collect(),
+            frames: 0,
+        }
+    }
+
+    /// `samples` holds whole interleaved frames.
+    pub fn process(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels.len()) {
+            for (channel, &sample) in self.channels.iter_mut().zip(frame) {
+                let level = sample.abs();
+                channel.peak = channel.peak.max(level);
+                channel.sum += sample as f64;
+                channel.squares += (sample as f64) * (sample as f64);
+
+                let clipping = level >= CLIP_LEVEL;
+                if clipping {
+                    channel.clipped_samples += 1;
+                    if !channel.clipping && channel.clip_frames.len() < MAX_CLIP_TIMES {
+                        channel.clip_frames.push(self.frames);
+                    }
+                }
+                channel.clipping = clipping;
+            }
+            self.frames += 1;
+        }
+    }
+
+    pub fn channel_reports(&self, sample_rate: u32) -> Vec<ChannelReport> {
+        let frames = self.frames.max(1) as f64;
+
+        self.channels
+            .iter()
+            .map(|channel| ChannelReport {
+                peak: channel.peak,
+                rms: (channel.squares / frames).sqrt() as f32,
+                dc_offset: (channel.sum / frames) as f32,
+                clipped_samples: channel.clipped_samples,
+                clip_times: channel
+                    .clip_frames
+                    .iter()
+                    .map(|&frame| frame as f64 / sample_rate as f64)
+                    .collect(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    f64::consts::PI,
+    time::{Instant, SystemTime},
+};
+
+use crate::{
+    source::{AudioSource, Gap, SourceError},
+    wav::WavFormat,
+};
+
+// Phases of the filter table, finer rate ratios round the position down to a phase
+const MAX_PHASES: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Quality {
+    // Zero crossings on each side of the sinc, Kaiser window beta and bandwidth kept below the
+    // Nyquist frequency of the lower rate
+    fn parameters(self) -> (f64, f64, f64) {
+        match self {
+            Quality::Low => (8.0, 6.0, 0.90),
+            Quality::Medium => (24.0, 8.6, 0.94),
+            Quality::High => (64.0, 12.0, 0.97),
+        }
+    }
+}
+
+/// Converts interleaved samples between sample rates with a windowed-sinc filter.
+pub struct Resampler {
+    channels: usize,
+    // Every input frame is `up` time units long and every output frame `down`
+    up: u64,
+    down: u64,
+    taps: usize,
+    phases: u64,
+    // `taps` coefficients for each phase
+    filter: Vec<f32>,
+    // Interleaved input frames not fully used yet
+    history: Vec<f32>,
+    // Time of the next output frame from the start of `history`, in time units
+    time: u64,
+    frames_in: u64,
+    frames_out: u64,
+}
+
+impl Resampler {
+    pub fn new(channels: u16, from_rate: u32, to_rate: u32, quality: Quality) -> Self {
+        assert!(channels > 0 && from_rate > 0 && to_rate > 0);
+
+        let divisor = gcd(from_rate as u64, to_rate as u64);
+        let up = to_rate as u64 / divisor;
+        let down = from_rate as u64 / divisor;
+        let phases = up.min(MAX_PHASES);
+
+        let (zero_crossings, beta, bandwidth) = quality.parameters();
+        // Cutoff relative to the input Nyquist frequency, lowered when downsampling
+        let cutoff = (up as f64 / down as f64).min(1.0) * bandwidth;
+        let half_taps = (zero_crossings / cutoff).ceil() as usize;
+        let taps = 2 * half_taps;
+
+        let mut filter = Vec::with_capacity(phases as usize * taps);
+        for phase in 0..phases {
+            let offset = phase as f64 / phases as f64;
+            let start = filter.len();
+            for tap in 0..taps {
+                // Distance from the output frame to the input frame of this tap
+                let x = tap as f64 - (half_taps - 1) as f64 - offset;
+                let window = kaiser(x / half_taps as f64, beta);
+                filter.push((cutoff * sinc(cutoff * x) * window) as f32);
+            }
+
+            // Unity gain at DC whatever the phase
+            let sum: f32 = filter[start..].iter().sum();
+            filter[start..].iter_mut().for_each(|c| *c /= sum);
+        }
+
+        Self {
+            channels: channels as usize,
+            up,
+            down,
+            taps,
+            phases,
+            filter,
+            // Centers the filter on the first input frame so that no delay is added
+            history: vec![0.0; (half_taps - 1) * channels as usize],
+            time: 0,
+            frames_in: 0,
+            frames_out: 0,
+        }
+    }
+
+    /// Resamples whole frames of `input`, appending to `output` whatever can be computed yet.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.history.extend_from_slice(input);
+        self.frames_in += (input.len() / self.channels) as u64;
+        self.produce(output, u64::MAX);
+    }
+
+    /// Appends the output frames still held back by the filter after the last input.
+    pub fn flush(&mut self, output: &mut Vec<f32>) {
+        let expected = (self.frames_in * self.up).div_ceil(self.down);
+        let padding = self.taps * self.channels;
+        while self.frames_out < expected {
+            self.history.resize(self.history.len() + padding, 0.0);
+            self.produce(output, expected);
+        }
+    }
+
+    fn produce(&mut self, output: &mut Vec<f32>, max_frames: u64) {
+        let available = (self.history.len() / self.channels) as u64;
+
+        while self.frames_out < max_frames {
+            let first = self.time / self.up;
+            if first + self.taps as u64 > available {
+                break;
+            }
+
+            let phase = (self.time % self.up) * self.phases / self.up;
+            let coefficients =
+                &self.filter[phase as usize * self.taps..(phase as usize + 1) * self.taps];
+            let frames = &self.history[first as usize * self.channels..];
+            for channel in 0..self.channels {
+                let sum = coefficients
+                    .iter()
+                    .zip(frames[channel..].iter().step_by(self.channels))
+                    .map(|(c, x)| c * x)
+                    .sum();
+                output.push(sum);
+            }
+
+            self.time += self.down;
+            self.frames_out += 1;
+        }
+
+        // Forget the frames no future output frame needs
+        let consumed = (self.time / self.up) as usize;
+        self.history.drain(..consumed * self.channels);
+        self.time -= consumed as u64 * self.up;
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+// `x` goes from -1 to 1 across the window
+fn kaiser(x: f64, beta: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(beta * (1.0 - x * x).sqrt()) / bessel_i0(beta)
+}
+
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-12 {
+        term *= (x / (2.0 * k)) * (x / (2.0 * k));
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Resamples another source to a different sample rate.
+pub struct ResampledSource {
+    inner: Box<dyn AudioSource>,
+    resampler: Resampler,
+    sample_rate: u32,
+    input: Vec<f32>,
+    // Resampled samples that didn't fit in the last `read`
+    pending: Vec<f32>,
+    position: usize,
+    exhausted: bool,
+}
+
+impl ResampledSource {
+    pub fn new(inner: Box<dyn AudioSource>, sample_rate: u32, quality: Quality) -> Self {
+        let resampler = Resampler::new(inner.channels(), inner.sample_rate(), sample_rate, quality);
+        let input = vec![0.0; 4096 * inner.channels() as usize];
+
+        Self {
+            inner,
+            resampler,
+            sample_rate,
+            input,
+            pending: Vec::new(),
+            position: 0,
+            exhausted: false,
+        }
+    }
+}
+
+impl AudioSource for ResampledSource {
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn native_format(&self) -> (WavFormat, u16) {
+        self.inner.native_format()
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        while self.position == self.pending.len() {
+            self.pending.clear();
+            self.position = 0;
+
+            if self.exhausted {
+                return Ok(None);
+            }
+            match self.inner.read(&mut self.input)? {
+                Some(0) => return Ok(Some(0)),
+                Some(len) => self
+                    .resampler
+                    .process(&self.input[..len], &mut self.pending),
+                None => {
+                    self.resampler.flush(&mut self.pending);
+                    self.exhausted = true;
+                }
+            }
+        }
+
+        let remaining = &self.pending[self.position..];
+        let len = remaining.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+
+        Ok(Some(len))
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+
+    fn take_dropped(&mut self) -> u64 {
+        self.inner.take_dropped()
+    }
+
+    fn take_gaps(&mut self) -> Vec<Gap> {
+        let (from, to) = (self.inner.sample_rate() as u64, self.sample_rate as u64);
+        let scale = |frames: u64| (frames as u128 * to as u128 / from as u128) as u64;
+
+        self.inner
+            .take_gaps()
+            .into_iter()
+            .map(|gap| Gap {
+                frame: scale(gap.frame),
+                frames: scale(gap.frames),
+                ..gap
+            })
+            .collect()
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.inner.take_errors()
+    }
+
+    fn last_capture(&self) -> Option<Instant> {
+        self.inner.last_capture()
+    }
+
+    fn start_time(&self) -> Option<SystemTime> {
+        self.inner.start_time()
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::{
+    atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// A fixed size single producer, single consumer queue of samples that never blocks nor
+/// allocates, so it can be fed from a realtime audio callback.
+///
+/// Samples are stored as the bits of `f32`s so the slots can be plain atomics.
+struct RingBuffer {
+    slots: Box<[AtomicU32]>,
+    // Total number of samples ever written and read, slots are indexed modulo the capacity
+    written: AtomicUsize,
+    read: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+pub fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    assert!(capacity > 0, "ring buffer capacity must not be zero");
+
+    let ring = Arc::new(RingBuffer {
+        slots: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+        written: AtomicUsize::new(0),
+        read: AtomicUsize::new(0),
+        dropped: AtomicU64::new(0),
+    });
+
+    (Producer { ring: ring.clone() }, Consumer { ring })
+}
+
+pub struct Producer {
+    ring: Arc<RingBuffer>,
+}
+
+impl Producer {
+    /// Number of samples that can be pushed right now.
+    pub fn free(&self) -> usize {
+        let ring = &*self.ring;
+        let written = ring.written.load(Ordering::Relaxed);
+        let read = ring.read.load(Ordering::Acquire);
+
+        ring.slots.len() - written.wrapping_sub(read)
+    }
+
+    /// Pushes the whole block or, if there isn't enough room left, drops it entirely so that
+    /// frames stay aligned. Returns whether the block was pushed.
+    pub fn push<I>(&mut self, samples: I) -> bool
+    where
+        I: IntoIterator<Item = f32>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let samples = samples.into_iter();
+        let len = samples.len();
+        let ring = &*self.ring;
+        let capacity = ring.slots.len();
+        let written = ring.written.load(Ordering::Relaxed);
+        let read = ring.read.load(Ordering::Acquire);
+
+        if capacity - written.wrapping_sub(read) < len {
+            ring.dropped.fetch_add(len as u64, Ordering::Relaxed);
+            return false;
+        }
+
+        for (i, sample) in samples.enumerate() {
+            let slot = &ring.slots[written.wrapping_add(i) % capacity];
+            slot.store(sample.to_bits(), Ordering::Relaxed);
+        }
+        ring.written
+            .store(written.wrapping_add(len), Ordering::Release);
+
+        true
+    }
+}
+
+pub struct Consumer {
+    ring: Arc<RingBuffer>,
+}
+
+impl Consumer {
+    /// Pops as many samples as available and fit in `buffer`, returns how many were popped.
+    pub fn pop(&mut self, buffer: &mut [f32]) -> usize {
+        let ring = &*self.ring;
+        let capacity = ring.slots.len();
+        let read = ring.read.load(Ordering::Relaxed);
+        let written = ring.written.load(Ordering::Acquire);
+
+        let len = written.wrapping_sub(read).min(buffer.len());
+        for (i, sample) in buffer[..len].iter_mut().enumerate() {
+            let slot = &ring.slots[read.wrapping_add(i) % capacity];
+            *sample = f32::from_bits(slot.load(Ordering::Relaxed));
+        }
+        ring.read.store(read.wrapping_add(len), Ordering::Release);
+
+        len
+    }
+
+    /// Number of samples dropped by the producer since the last call.
+    pub fn take_dropped(&mut self) -> u64 {
+        self.ring.dropped.swap(0, Ordering::Relaxed)
+    }
+}
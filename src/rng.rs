@@ -0,0 +1,26 @@
+/// xorshift64*, plenty for noise and dither, and reproducible across platforms.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+        // The state must never be zero
+        match seed ^ GOLDEN_GAMMA {
+            0 => Rng(GOLDEN_GAMMA),
+            state => Rng(state),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
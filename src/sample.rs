@@ -0,0 +1,111 @@
+use crate::{
+    serialize::{BinaryDeserialize, BinarySerialize},
+    wav::WavFormat,
+};
+
+/// A sample type that can be stored as is in the `data` chunk of a .wav file.
+///
+/// Conversions between sample types go through `f32` in the [-1.0, 1.0] range.
+pub trait Sample: Copy + BinarySerialize + BinaryDeserialize {
+    const FORMAT: WavFormat;
+    const BITS_PER_SAMPLE: u16;
+
+    fn to_f32(self) -> f32;
+    /// Out of range values are clipped.
+    fn from_f32(value: f32) -> Self;
+}
+
+fn int_from_f32(value: f32, bits: u16) -> i32 {
+    let scale = (1i64 << (bits - 1)) as f32;
+    let max = ((1i64 << (bits - 1)) - 1) as f32;
+
+    (value * scale).round().clamp(-scale, max) as i32
+}
+
+impl Sample for i16 {
+    const FORMAT: WavFormat = WavFormat::Pcm;
+    const BITS_PER_SAMPLE: u16 = 16;
+
+    fn to_f32(self) -> f32 {
+        self as f32 / 32768.0
+    }
+
+    fn from_f32(value: f32) -> Self {
+        int_from_f32(value, 16) as i16
+    }
+}
+
+/// A 24-bit signed integer sample, packed on 3 bytes in .wav files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I24(pub i32);
+
+impl I24 {
+    pub const MIN: i32 = -(1 << 23);
+    pub const MAX: i32 = (1 << 23) - 1;
+}
+
+impl Sample for I24 {
+    const FORMAT: WavFormat = WavFormat::Pcm;
+    const BITS_PER_SAMPLE: u16 = 24;
+
+    fn to_f32(self) -> f32 {
+        self.0 as f32 / 8388608.0
+    }
+
+    fn from_f32(value: f32) -> Self {
+        I24(int_from_f32(value, 24))
+    }
+}
+
+impl BinarySerialize for I24 {
+    fn needed_size(&self) -> usize {
+        3
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() || self.0 < I24::MIN || self.0 > I24::MAX {
+            return Err(());
+        }
+
+        buffer[0..3].copy_from_slice(&self.0.to_le_bytes()[0..3]);
+
+        Ok(())
+    }
+}
+
+impl BinaryDeserialize for I24 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..3).ok_or(())?;
+        let sign = if bytes[2] & 0x80 != 0 { 0xff } else { 0 };
+
+        Ok(I24(i32::from_le_bytes([
+            bytes[0], bytes[1], bytes[2], sign,
+        ])))
+    }
+}
+
+impl Sample for i32 {
+    const FORMAT: WavFormat = WavFormat::Pcm;
+    const BITS_PER_SAMPLE: u16 = 32;
+
+    fn to_f32(self) -> f32 {
+        self as f32 / 2147483648.0
+    }
+
+    fn from_f32(value: f32) -> Self {
+        int_from_f32(value, 32)
+    }
+}
+
+impl Sample for f32 {
+    const FORMAT: WavFormat = WavFormat::IeeeFloat;
+    const BITS_PER_SAMPLE: u16 = 32;
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
@@ -0,0 +1,206 @@
+pub trait BinarySerialize {
+    fn needed_size(&self) -> usize;
+    #[allow(clippy::result_unit_err)]
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()>;
+}
+
+// Text chunks hold a NUL terminated string and are word aligned
+pub(crate) fn text_size(text: &str) -> usize {
+    let size = text.len() + 1;
+    size + (size & 1)
+}
+
+impl<T: BinarySerialize> BinarySerialize for [T] {
+    fn needed_size(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+
+        self.len() * self[0].needed_size()
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        let mut off = 0;
+        for val in self {
+            val.serialize(&mut buffer[off..off + val.needed_size()])?;
+            off += val.needed_size();
+        }
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for u64 {
+    fn needed_size(&self) -> usize {
+        8
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..8].copy_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for u32 {
+    fn needed_size(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..4].copy_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for u16 {
+    fn needed_size(&self) -> usize {
+        2
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..2].copy_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for i16 {
+    fn needed_size(&self) -> usize {
+        2
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..2].copy_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for i32 {
+    fn needed_size(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..4].copy_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for f32 {
+    fn needed_size(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0..4].copy_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for u8 {
+    fn needed_size(&self) -> usize {
+        1
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        buffer[0] = *self;
+
+        Ok(())
+    }
+}
+
+pub trait BinaryDeserialize: Sized {
+    #[allow(clippy::result_unit_err)]
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()>;
+}
+
+impl BinaryDeserialize for u64 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..8).ok_or(())?;
+
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl BinaryDeserialize for u32 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..4).ok_or(())?;
+
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl BinaryDeserialize for u16 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..2).ok_or(())?;
+
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl BinaryDeserialize for i16 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..2).ok_or(())?;
+
+        Ok(i16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl BinaryDeserialize for i32 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..4).ok_or(())?;
+
+        Ok(i32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl BinaryDeserialize for f32 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        let bytes = buffer.get(0..4).ok_or(())?;
+
+        Ok(f32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl BinaryDeserialize for u8 {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        buffer.first().copied().ok_or(())
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    f64::consts::TAU,
+    fmt,
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::Path,
+    time::{Instant, SystemTime},
+};
+
+use crate::{
+    reader::{WavError, WavReader},
+    rng::Rng,
+    sample::{Sample, I24},
+    wav::WavFormat,
+};
+
+#[derive(Debug)]
+pub enum SourceError {
+    Wav(WavError),
+    Stream(String),
+    /// The device went away, e.g. it was unplugged.
+    Disconnected,
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::Wav(err) => write!(f, "failed to read .wav source: {}", err),
+            SourceError::Stream(err) => write!(f, "audio stream failed: {}", err),
+            SourceError::Disconnected => write!(f, "input device disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl From<WavError> for SourceError {
+    fn from(err: WavError) -> Self {
+        SourceError::Wav(err)
+    }
+}
+
+/// A discontinuity in the audio of a source, e.g. when the device skipped a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    /// Frames the source delivered before the gap.
+    pub frame: u64,
+    /// Length of the gap in frames.
+    pub frames: u64,
+    /// Whether the gap was filled with silence, i.e. the timeline is still sample-accurate.
+    pub filled: bool,
+}
+
+/// Something that produces interleaved `f32` samples to record, be it a sound card, a file or a
+/// generator.
+pub trait AudioSource {
+    fn channels(&self) -> u16;
+    fn sample_rate(&self) -> u32;
+
+    /// Sample format the source delivers before conversion to `f32`, used as the output format
+    /// when none is requested.
+    fn native_format(&self) -> (WavFormat, u16) {
+        (WavFormat::IeeeFloat, 32)
+    }
+
+    /// Fills the start of `buffer`, whose length is a multiple of the channel count, with whole
+    /// frames and returns how many samples were written. Live sources return `Some(0)` while
+    /// waiting for more audio, `None` means the source is exhausted.
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError>;
+
+    /// Live sources capture in real time and keep going until stopped, the others can be read
+    /// as fast as possible.
+    fn is_live(&self) -> bool {
+        false
+    }
+
+    /// Asks a live source to stop capturing: what is already buffered can still be read, then
+    /// `read` returns `None`.
+    fn stop(&mut self) {}
+
+    /// Number of samples lost since the last call because they were not read in time.
+    fn take_dropped(&mut self) -> u64 {
+        0
+    }
+
+    /// Gaps detected since the last call.
+    fn take_gaps(&mut self) -> Vec<Gap> {
+        Vec::new()
+    }
+
+    /// Errors reported by the source since the last call, that didn't stop it.
+    fn take_errors(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// When a live source last delivered audio, to know how long it was gone after a failure.
+    fn last_capture(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Wall clock time of the first sample of a live source.
+    fn start_time(&self) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Plays back samples held in memory, mostly useful for tests.
+pub struct MemorySource {
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<f32>,
+    position: usize,
+}
+
+impl MemorySource {
+    pub fn new(channels: u16, sample_rate: u32, samples: Vec<f32>) -> Self {
+        Self {
+            channels,
+            sample_rate,
+            samples,
+            position: 0,
+        }
+    }
+}
+
+impl AudioSource for MemorySource {
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        let remaining = &self.samples[self.position..];
+        if remaining.is_empty() {
+            return Ok(None);
+        }
+
+        let len = remaining.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+
+        Ok(Some(len))
+    }
+}
+
+/// Reads the samples of a .wav file, whatever their format.
+pub struct FileSource<R: Read + Seek> {
+    reader: WavReader<R>,
+}
+
+impl FileSource<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SourceError> {
+        Ok(Self::new(WavReader::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> FileSource<R> {
+    pub fn new(reader: WavReader<R>) -> Self {
+        Self { reader }
+    }
+
+    fn read_as<S: Sample>(&mut self, buffer: &mut [f32]) -> Result<usize, WavError> {
+        let mut len = 0;
+        for (slot, sample) in buffer.iter_mut().zip(self.reader.samples::<S>()?) {
+            *slot = sample?.to_f32();
+            len += 1;
+        }
+
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> AudioSource for FileSource<R> {
+    fn channels(&self) -> u16 {
+        self.reader.file().channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.reader.file().sample_rate
+    }
+
+    fn native_format(&self) -> (WavFormat, u16) {
+        (
+            self.reader.file().format,
+            self.reader.file().bits_per_sample,
+        )
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        let len = match self.native_format() {
+            (WavFormat::Pcm, 16) => self.read_as::<i16>(buffer)?,
+            (WavFormat::Pcm, 24) => self.read_as::<I24>(buffer)?,
+            (WavFormat::Pcm, 32) => self.read_as::<i32>(buffer)?,
+            (WavFormat::IeeeFloat, 32) => self.read_as::<f32>(buffer)?,
+            (_, bits) => return Err(WavError::UnsupportedBitDepth(bits).into()),
+        };
+
+        Ok(if len == 0 { None } else { Some(len) })
+    }
+}
+
+/// An endless sine wave, the same on every channel.
+pub struct SineSource {
+    channels: u16,
+    sample_rate: u32,
+    frequency: f64,
+    amplitude: f32,
+    phase: f64,
+}
+
+impl SineSource {
+    pub fn new(channels: u16, sample_rate: u32, frequency: f64, amplitude: f32) -> Self {
+        Self {
+            channels,
+            sample_rate,
+            frequency,
+            amplitude,
+            phase: 0.0,
+        }
+    }
+}
+
+impl AudioSource for SineSource {
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        let step = TAU * self.frequency / self.sample_rate as f64;
+        for frame in buffer.chunks_exact_mut(self.channels as usize) {
+            frame.fill(self.amplitude * self.phase.sin() as f32);
+            self.phase = (self.phase + step) % TAU;
+        }
+
+        Ok(Some(buffer.len()))
+    }
+}
+
+/// Endless white noise, independent on every channel. The same seed gives the same samples.
+pub struct NoiseSource {
+    channels: u16,
+    sample_rate: u32,
+    amplitude: f32,
+    rng: Rng,
+}
+
+impl NoiseSource {
+    pub fn new(channels: u16, sample_rate: u32, amplitude: f32, seed: u64) -> Self {
+        Self {
+            channels,
+            sample_rate,
+            amplitude,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl AudioSource for NoiseSource {
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        for sample in buffer.iter_mut() {
+            *sample = self.amplitude * (self.rng.next_f32() * 2.0 - 1.0);
+        }
+
+        Ok(Some(buffer.len()))
+    }
+}
@@ -0,0 +1,126 @@
+use std::{fs, path::Path};
+
+use record_wav::report::Report;
+use serde_json::{json, Value};
+
+// Clip times printed per channel, the JSON report has all the kept ones
+const PRINTED_CLIP_TIMES: usize = 10;
+
+pub fn print_report(report: &Report) {
+    println!(
+        "Recorded {:.3} s ({} frames at {} Hz)",
+        report.duration(),
+        report.frames,
+        report.sample_rate
+    );
+    println!("  ch  peak dBFS  rms dBFS  dc offset  clipped");
+    for (i, channel) in report.channels.iter().enumerate() {
+        println!(
+            "  {:>2}  {:>9}  {:>8}  {:>+9.5}  {:>7}",
+            i + 1,
+            format_db(channel.peak),
+            format_db(channel.rms),
+            channel.dc_offset,
+            channel.clipped_samples
+        );
+    }
+
+    for (i, channel) in report.channels.iter().enumerate() {
+        if channel.clip_times.is_empty() {
+            continue;
+        }
+
+        let mut times = channel
+            .clip_times
+            .iter()
+            .take(PRINTED_CLIP_TIMES)
+            .map(|time| format!("{:.3} s", time))
+            .collect::<Vec<_>>();
+        if channel.clip_times.len() > PRINTED_CLIP_TIMES {
+            times.push("...".to_string());
+        }
+        println!("Clipping on channel {} at {}", i + 1, times.join(", "));
+    }
+
+    for gap in &report.gaps {
+        println!(
+            "Gap of {:.1} ms at {:.3} s{}",
+            gap.frames as f64 * 1000.0 / report.sample_rate as f64,
+            gap.frame as f64 / report.sample_rate as f64,
+            if gap.filled {
+                ", filled with silence"
+            } else {
+                ""
+            }
+        );
+    }
+    if report.dropped > 0 {
+        println!(
+            "{} samples were dropped, the recording has gaps",
+            report.dropped
+        );
+    }
+    for error in &report.errors {
+        println!("Stream error: {}", error);
+    }
+}
+
+pub fn write_report(report: &Report, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&report_json(report)).unwrap();
+
+    fs::write(path, json + "\n")
+        .map_err(|err| format!("failed to write {}: {}", path.display(), err))
+}
+
+fn report_json(report: &Report) -> Value {
+    let channels = report
+        .channels
+        .iter()
+        .map(|channel| {
+            json!({
+                "peak": channel.peak,
+                "peak_dbfs": to_dbfs(channel.peak),
+                "rms": channel.rms,
+                "rms_dbfs": to_dbfs(channel.rms),
+                "dc_offset": channel.dc_offset,
+                "clipped_samples": channel.clipped_samples,
+                "clip_times": channel.clip_times,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let gaps = report
+        .gaps
+        .iter()
+        .map(|gap| {
+            json!({
+                "time": gap.frame as f64 / report.sample_rate as f64,
+                "frame": gap.frame,
+                "frames": gap.frames,
+                "filled": gap.filled,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "duration": report.duration(),
+        "frames": report.frames,
+        "sample_rate": report.sample_rate,
+        "dropped_samples": report.dropped,
+        "channels": channels,
+        "gaps": gaps,
+        "stream_errors": report.errors,
+    })
+}
+
+// Silence has no level in dB, it is null in JSON
+fn to_dbfs(level: f32) -> Option<f32> {
+    (level > 0.0).then(|| 20.0 * level.log10())
+}
+
+fn format_db(level: f32) -> String {
+    match to_dbfs(level) {
+        Some(db) => format!("{:.1}", db),
+        None => "-inf".to_string(),
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    bext::Bext,
+    info::Info,
+    serialize::{BinaryDeserialize, BinarySerialize},
+};
+
+pub const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+/// `KSDATAFORMAT_SUBTYPE_*` GUIDs are the format tag followed by these 14 bytes.
+pub const SUBFORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+pub struct WavFile {
+    pub format: WavFormat,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// Speaker positions of the channels, only written in extensible headers.
+    pub channel_mask: u32,
+    /// Reserve room for a `ds64` chunk so the file can become RF64 if it grows past 4 GiB.
+    pub reserve_ds64: bool,
+    /// Size in bytes of the `data` chunk payload, excluding the pad byte.
+    pub data_size: u64,
+    /// Size in bytes of the chunks that follow the `data` chunk.
+    pub trailer_size: u64,
+    /// Written in a `LIST`/`INFO` chunk before the samples.
+    pub info: Info,
+    /// Broadcast Wave metadata, written in a `bext` chunk before the format.
+    pub bext: Option<Bext>,
+}
+
+impl WavFile {
+    pub fn new(format: WavFormat, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Self {
+        Self {
+            format,
+            channels,
+            sample_rate,
+            bits_per_sample,
+            channel_mask: default_channel_mask(channels),
+            reserve_ds64: true,
+            data_size: 0,
+            trailer_size: 0,
+            info: Info::default(),
+            bext: None,
+        }
+    }
+
+    /// Size stored in the RIFF header, i.e. everything after it.
+    pub fn riff_size(&self) -> u64 {
+        // Chunks are word aligned, an odd sized `data` chunk is followed by a pad byte
+        let padded_data_size = self.data_size + (self.data_size & 1);
+
+        (self.needed_size() as u64 - 8) + padded_data_size + self.trailer_size
+    }
+
+    /// Sizes that don't fit the 32-bit RIFF fields are moved to a `ds64` chunk.
+    pub fn is_rf64(&self) -> bool {
+        self.riff_size() > u32::MAX as u64
+    }
+
+    /// Plain headers are ambiguous with more than 2 channels or more than 16 bits per PCM
+    /// sample.
+    pub fn is_extensible(&self) -> bool {
+        self.channels > 2 || (self.format == WavFormat::Pcm && self.bits_per_sample > 16)
+    }
+
+    /// Size of a frame in bytes, `None` if it doesn't fit the 16-bit header field.
+    pub fn frame_size(&self) -> Option<u16> {
+        (self.bits_per_sample as u32 * self.channels as u32 / 8)
+            .try_into()
+            .ok()
+    }
+
+    /// Panics if the frame size doesn't fit, see `frame_size`.
+    pub fn block_align(&self) -> u16 {
+        self.frame_size()
+            .expect("frame size does not fit in 16 bits")
+    }
+
+    pub fn avg_bytes_per_sec(&self) -> u32 {
+        let bytes = self.sample_rate as u64 * self.block_align() as u64;
+        bytes.min(u32::MAX as u64) as u32
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.data_size / self.block_align() as u64
+    }
+
+    fn fmt_size(&self) -> u32 {
+        match self.format {
+            _ if self.is_extensible() => 40,
+            WavFormat::Pcm => 16,
+            // Non-PCM formats carry a `cbSize` field, even if it is zero
+            WavFormat::IeeeFloat => 18,
+        }
+    }
+
+    // Every format except PCM requires a `fact` chunk
+    fn has_fact(&self) -> bool {
+        self.format != WavFormat::Pcm
+    }
+}
+
+impl BinarySerialize for WavFile {
+    // Only the header: the samples are streamed right after it by `WavWriter`
+    fn needed_size(&self) -> usize {
+        let ds64_size = if self.reserve_ds64 { 36 } else { 0 };
+        let fact_size = if self.has_fact() { 12 } else { 0 };
+        let bext_size = self.bext.as_ref().map_or(0, |bext| bext.needed_size());
+
+        12 + ds64_size
+            + bext_size
+            + 8
+            + self.fmt_size() as usize
+            + fact_size
+            + self.info.needed_size()
+            + 8
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        // `block_align` panics otherwise
+        if self.frame_size().is_none() {
+            return Err(());
+        }
+        let rf64 = self.is_rf64();
+        if rf64 && !self.reserve_ds64 {
+            return Err(());
+        }
+        // In RF64 files the real sizes are in `ds64` and the 32-bit fields are all ones
+        let clamp = |size: u64| if rf64 { u32::MAX } else { size as u32 };
+
+        buffer[0..4].copy_from_slice(if rf64 { b"RF64" } else { b"RIFF" });
+        clamp(self.riff_size()).serialize(&mut buffer[4..8])?;
+        buffer[8..12].copy_from_slice(b"WAVE");
+        let mut off = 12;
+
+        if self.reserve_ds64 {
+            // Left as a `JUNK` chunk that readers skip while the file stays small
+            buffer[off..off + 4].copy_from_slice(if rf64 { b"ds64" } else { b"JUNK" });
+            28u32.serialize(&mut buffer[off + 4..off + 8])?;
+            buffer[off + 8..off + 36].fill(0);
+            if rf64 {
+                self.riff_size().serialize(&mut buffer[off + 8..off + 16])?;
+                self.data_size.serialize(&mut buffer[off + 16..off + 24])?;
+                self.frames().serialize(&mut buffer[off + 24..off + 32])?;
+                // No table entries for other chunks
+                0u32.serialize(&mut buffer[off + 32..off + 36])?;
+            }
+            off += 36;
+        }
+
+        if let Some(bext) = &self.bext {
+            bext.serialize(&mut buffer[off..off + bext.needed_size()])?;
+            off += bext.needed_size();
+        }
+
+        buffer[off..off + 4].copy_from_slice(b"fmt ");
+        self.fmt_size().serialize(&mut buffer[off + 4..off + 8])?;
+        if self.is_extensible() {
+            WAVE_FORMAT_EXTENSIBLE.serialize(&mut buffer[off + 8..off + 10])?;
+        } else {
+            self.format.serialize(&mut buffer[off + 8..off + 10])?;
+        }
+        self.channels.serialize(&mut buffer[off + 10..off + 12])?;
+        self.sample_rate
+            .serialize(&mut buffer[off + 12..off + 16])?;
+        self.avg_bytes_per_sec()
+            .serialize(&mut buffer[off + 16..off + 20])?;
+        self.block_align()
+            .serialize(&mut buffer[off + 20..off + 22])?;
+        self.bits_per_sample
+            .serialize(&mut buffer[off + 22..off + 24])?;
+        off += 24;
+        if self.fmt_size() == 18 {
+            0u16.serialize(&mut buffer[off..off + 2])?;
+            off += 2;
+        } else if self.fmt_size() == 40 {
+            22u16.serialize(&mut buffer[off..off + 2])?;
+            // Valid bits, every bit of the container is used
+            self.bits_per_sample
+                .serialize(&mut buffer[off + 2..off + 4])?;
+            self.channel_mask.serialize(&mut buffer[off + 4..off + 8])?;
+            self.format.serialize(&mut buffer[off + 8..off + 10])?;
+            buffer[off + 10..off + 24].copy_from_slice(&SUBFORMAT_GUID_TAIL);
+            off += 24;
+        }
+
+        if self.has_fact() {
+            buffer[off..off + 4].copy_from_slice(b"fact");
+            4u32.serialize(&mut buffer[off + 4..off + 8])?;
+            clamp(self.frames()).serialize(&mut buffer[off + 8..off + 12])?;
+            off += 12;
+        }
+
+        self.info
+            .serialize(&mut buffer[off..off + self.info.needed_size()])?;
+        off += self.info.needed_size();
+
+        buffer[off..off + 4].copy_from_slice(b"data");
+        clamp(self.data_size).serialize(&mut buffer[off + 4..off + 8])?;
+
+        Ok(())
+    }
+}
+
+/// The usual speaker layout for a given channel count, no positions past 7.1.
+pub fn default_channel_mask(channels: u16) -> u32 {
+    const FRONT_LEFT: u32 = 0x1;
+    const FRONT_RIGHT: u32 = 0x2;
+    const FRONT_CENTER: u32 = 0x4;
+    const LOW_FREQUENCY: u32 = 0x8;
+    const BACK_LEFT: u32 = 0x10;
+    const BACK_RIGHT: u32 = 0x20;
+    const BACK_CENTER: u32 = 0x100;
+    const SIDE_LEFT: u32 = 0x200;
+    const SIDE_RIGHT: u32 = 0x400;
+
+    match channels {
+        1 => FRONT_CENTER,
+        2 => FRONT_LEFT | FRONT_RIGHT,
+        3 => FRONT_LEFT | FRONT_RIGHT | FRONT_CENTER,
+        4 => FRONT_LEFT | FRONT_RIGHT | BACK_LEFT | BACK_RIGHT,
+        5 => FRONT_LEFT | FRONT_RIGHT | FRONT_CENTER | BACK_LEFT | BACK_RIGHT,
+        6 => FRONT_LEFT | FRONT_RIGHT | FRONT_CENTER | LOW_FREQUENCY | BACK_LEFT | BACK_RIGHT,
+        7 => {
+            FRONT_LEFT
+                | FRONT_RIGHT
+                | FRONT_CENTER
+                | LOW_FREQUENCY
+                | BACK_CENTER
+                | SIDE_LEFT
+                | SIDE_RIGHT
+        }
+        8 => {
+            FRONT_LEFT
+                | FRONT_RIGHT
+                | FRONT_CENTER
+                | LOW_FREQUENCY
+                | BACK_LEFT
+                | BACK_RIGHT
+                | SIDE_LEFT
+                | SIDE_RIGHT
+        }
+        _ => 0,
+    }
+}
+
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavFormat {
+    Pcm = 1,
+    IeeeFloat = 3,
+}
+
+impl BinarySerialize for WavFormat {
+    fn needed_size(&self) -> usize {
+        2
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) -> Result<(), ()> {
+        if buffer.len() < self.needed_size() {
+            return Err(());
+        }
+
+        (*self as u16).serialize(buffer)?;
+
+        Ok(())
+    }
+}
+
+impl BinaryDeserialize for WavFormat {
+    fn deserialize(buffer: &[u8]) -> Result<Self, ()> {
+        match u16::deserialize(buffer)? {
+            1 => Ok(WavFormat::Pcm),
+            3 => Ok(WavFormat::IeeeFloat),
+            _ => Err(()),
+        }
+    }
+}
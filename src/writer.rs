@@ -0,0 +1,173 @@
+use std::{
+    io::{self, Seek, SeekFrom, Write},
+    time::SystemTime,
+};
+
+use crate::{
+    bext::Bext,
+    cue::{Cue, Cues},
+    dither::Dither,
+    sample::{Sample, I24},
+    serialize::BinarySerialize,
+    wav::{WavFile, WavFormat},
+};
+
+/// Writes a .wav file incrementally: a placeholder header is written first, sample blocks are
+/// appended as they arrive and the chunk sizes are patched by `finalize`.
+pub struct WavWriter<W: Write + Seek> {
+    inner: W,
+    file: WavFile,
+    // Reused for every block so memory use does not depend on the recording length
+    scratch: Vec<u8>,
+    dither: Option<Dither>,
+    cues: Cues,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut inner: W, mut file: WavFile) -> io::Result<Self> {
+        if !matches!(
+            (file.format, file.bits_per_sample),
+            (WavFormat::Pcm, 16 | 24 | 32) | (WavFormat::IeeeFloat, 32)
+        ) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported sample format",
+            ));
+        }
+        if file.frame_size().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "too many channels",
+            ));
+        }
+
+        file.data_size = 0;
+
+        let mut header = vec![0u8; file.needed_size()];
+        file.serialize(&mut header)
+            .expect("header buffer is too small");
+        inner.write_all(&header)?;
+
+        Ok(Self {
+            inner,
+            file,
+            scratch: Vec::new(),
+            dither: None,
+            cues: Cues::default(),
+        })
+    }
+
+    /// Dithers the samples when they are converted to 16 or 24-bit integers.
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = Some(dither);
+        self
+    }
+
+    pub fn file(&self) -> &WavFile {
+        &self.file
+    }
+
+    /// Moves the origination date and time of the `bext` chunk, if any, to when the first
+    /// sample was captured.
+    pub fn set_origination(&mut self, time: SystemTime) {
+        let sample_rate = self.file.sample_rate;
+        if let Some(bext) = &mut self.file.bext {
+            let originated = Bext::originated_at(time, sample_rate);
+            bext.origination_date = originated.origination_date;
+            bext.origination_time = originated.origination_time;
+            bext.time_reference = originated.time_reference;
+        }
+    }
+
+    /// Marks a position in the recording. Cue positions are 32-bit, those past the first 2^32
+    /// frames are ignored.
+    pub fn add_cue(&mut self, frame: u64, label: impl Into<String>, note: Option<String>) {
+        if let Ok(frame) = frame.try_into() {
+            self.cues.0.push(Cue {
+                frame,
+                label: label.into(),
+                note,
+            });
+        }
+    }
+
+    /// Samples are converted to the sample format of the file if needed.
+    pub fn write_samples<S: Sample>(&mut self, samples: &[S]) -> io::Result<()> {
+        match (self.file.format, self.file.bits_per_sample) {
+            (WavFormat::Pcm, 16) => self.write_as::<S, i16>(samples),
+            (WavFormat::Pcm, 24) => self.write_as::<S, I24>(samples),
+            (WavFormat::Pcm, 32) => self.write_as::<S, i32>(samples),
+            (WavFormat::IeeeFloat, 32) => self.write_as::<S, f32>(samples),
+            _ => unreachable!("sample format is checked by WavWriter::new"),
+        }
+    }
+
+    fn write_as<S: Sample, T: Sample>(&mut self, samples: &[S]) -> io::Result<()> {
+        let sample_size = T::BITS_PER_SAMPLE as usize / 8;
+        let size = samples.len() * sample_size;
+        self.scratch.resize(size, 0);
+        // An `f32` doesn't have the precision to dither 32-bit integers
+        let mut dither = self
+            .dither
+            .as_mut()
+            .filter(|_| T::FORMAT == WavFormat::Pcm && T::BITS_PER_SAMPLE < 32);
+        for (sample, bytes) in samples
+            .iter()
+            .zip(self.scratch.chunks_exact_mut(sample_size))
+        {
+            let value = match &mut dither {
+                Some(dither) => dither.process(sample.to_f32(), T::BITS_PER_SAMPLE),
+                None => sample.to_f32(),
+            };
+            T::from_f32(value)
+                .serialize(bytes)
+                .expect("scratch buffer is too small");
+        }
+
+        // Nothing is written if the file can't hold the block, and `data_size` only counts what
+        // made it to `inner`
+        let data_size = self.file.data_size;
+        self.file.data_size += size as u64;
+        let result = if self.file.is_rf64() && !self.file.reserve_ds64 {
+            Err(io::Error::other(
+                "file would exceed 4 GiB and no room was reserved for RF64",
+            ))
+        } else {
+            self.inner.write_all(&self.scratch)
+        };
+        if result.is_err() {
+            self.file.data_size = data_size;
+        }
+
+        result
+    }
+
+    pub fn finalize(mut self) -> io::Result<W> {
+        if self.file.data_size % 2 == 1 {
+            self.inner.write_all(&[0])?;
+        }
+
+        let mut trailer = vec![0u8; self.cues.needed_size()];
+        self.cues
+            .serialize(&mut trailer)
+            .expect("trailer buffer is too small");
+        self.file.trailer_size = trailer.len() as u64;
+        if self.file.is_rf64() && !self.file.reserve_ds64 {
+            return Err(io::Error::other(
+                "file would exceed 4 GiB and no room was reserved for RF64",
+            ));
+        }
+        self.inner.write_all(&trailer)?;
+
+        let mut header = vec![0u8; self.file.needed_size()];
+        self.file
+            .serialize(&mut header)
+            .expect("header buffer is too small");
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.write_all(&header)?;
+        self.inner.seek(SeekFrom::End(0))?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    io::Cursor,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use record_wav::{
+    bext::Bext,
+    reader::WavReader,
+    serialize::{BinaryDeserialize, BinarySerialize},
+    wav::{WavFile, WavFormat},
+    writer::WavWriter,
+};
+
+fn bext() -> Bext {
+    Bext {
+        description: "Interview".to_string(),
+        originator: "record-wav".to_string(),
+        originator_reference: "take-1".to_string(),
+        ..Bext::originated_at(UNIX_EPOCH + Duration::from_millis(951_827_696_500), 48000)
+    }
+}
+
+#[test]
+fn originated_at() {
+    let bext = Bext::originated_at(UNIX_EPOCH + Duration::from_millis(951_827_696_500), 48000);
+
+    assert_eq!(bext.origination_date, "2000-02-29");
+    assert_eq!(bext.origination_time, "12:34:56");
+    assert_eq!(bext.time_reference, 45296 * 48000 + 24000);
+}
+
+#[test]
+fn origination_dates() {
+    let date = |secs| Bext::originated_at(UNIX_EPOCH + Duration::from_secs(secs), 48000);
+
+    assert_eq!(date(0).origination_date, "1970-01-01");
+    assert_eq!(date(0).origination_time, "00:00:00");
+    assert_eq!(date(946_684_799).origination_date, "1999-12-31");
+    assert_eq!(date(946_684_799).origination_time, "23:59:59");
+    assert_eq!(date(946_684_799).time_reference, 86399 * 48000);
+    assert_eq!(date(951_868_800).origination_date, "2000-03-01");
+    // Not a leap year
+    assert_eq!(date(4_107_456_000).origination_date, "2100-02-28");
+    assert_eq!(date(4_107_542_400).origination_date, "2100-03-01");
+}
+
+#[test]
+fn layout() {
+    let bext = Bext {
+        // Cut to the 32 bytes of the field
+        originator: "x".repeat(40),
+        ..bext()
+    };
+    let mut bytes = vec![0xffu8; bext.needed_size()];
+    bext.serialize(&mut bytes).unwrap();
+
+    assert_eq!(bytes.len(), 8 + 602);
+    assert_eq!(&bytes[0..4], b"bext");
+    assert_eq!(u32::deserialize(&bytes[4..8]).unwrap(), 602);
+
+    let body = &bytes[8..];
+    assert_eq!(&body[0..9], b"Interview");
+    assert!(body[9..256].iter().all(|&b| b == 0));
+    assert_eq!(&body[256..288], "x".repeat(32).as_bytes());
+    assert_eq!(&body[288..294], b"take-1");
+    assert_eq!(&body[320..330], b"2000-02-29");
+    assert_eq!(&body[330..338], b"12:34:56");
+    assert_eq!(
+        u64::deserialize(&body[338..346]).unwrap(),
+        bext.time_reference
+    );
+    assert_eq!(u16::deserialize(&body[346..348]).unwrap(), 1);
+    assert!(body[348..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn round_trip() {
+    let mut file = WavFile::new(WavFormat::Pcm, 1, 48000, 16);
+    file.bext = Some(bext());
+    let mut writer = WavWriter::new(Cursor::new(Vec::new()), file).unwrap();
+    writer.write_samples(&[1i16, 2, 3]).unwrap();
+    let bytes = writer.finalize().unwrap().into_inner();
+
+    let mut reader = WavReader::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(reader.file().bext, Some(bext()));
+    assert_eq!(reader.read_samples::<i16>().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn too_small() {
+    assert_eq!(Bext::parse(&[0; 345]), None);
+}
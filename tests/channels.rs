@@ -0,0 +1,85 @@
+use record_wav::{
+    channels::{ChannelMap, Downmix, MappedSource},
+    source::{AudioSource, MemorySource},
+};
+
+// Three channels, the tens are the frame and the units the channel
+const INPUT: [f32; 9] = [0.0, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+
+fn map(selection: Option<&[u16]>, downmix: Option<Downmix>) -> (u16, Vec<f32>) {
+    let source = MemorySource::new(3, 48000, INPUT.to_vec());
+    let mut source = MappedSource::new(Box::new(source), ChannelMap::new(3, selection, downmix));
+
+    // Not a whole number of output frames, only whole frames are read
+    let mut buffer = [0.0; 5];
+    let mut samples = Vec::new();
+    while let Some(len) = source.read(&mut buffer).unwrap() {
+        samples.extend_from_slice(&buffer[..len]);
+    }
+
+    (source.channels(), samples)
+}
+
+fn assert_close(left: &[f32], right: &[f32]) {
+    assert_eq!(left.len(), right.len(), "{:?} != {:?}", left, right);
+    for (l, r) in left.iter().zip(right) {
+        assert!((l - r).abs() < 1e-4, "{:?} != {:?}", left, right);
+    }
+}
+
+#[test]
+fn all_channels() {
+    assert_eq!(map(None, None), (3, INPUT.to_vec()));
+}
+
+#[test]
+fn select() {
+    assert_eq!(map(Some(&[2]), None), (1, vec![2.0, 12.0, 22.0]));
+}
+
+#[test]
+fn reorder() {
+    assert_eq!(
+        map(Some(&[2, 0]), None),
+        (2, vec![2.0, 0.0, 12.0, 10.0, 22.0, 20.0])
+    );
+}
+
+#[test]
+fn duplicate() {
+    assert_eq!(
+        map(Some(&[1, 1]), None),
+        (2, vec![1.0, 1.0, 11.0, 11.0, 21.0, 21.0])
+    );
+}
+
+#[test]
+fn average() {
+    let (channels, samples) = map(Some(&[0, 2]), Some(Downmix::Average));
+
+    assert_eq!(channels, 1);
+    assert_close(&samples, &[1.0, 11.0, 21.0]);
+}
+
+#[test]
+fn sum() {
+    let (channels, samples) = map(None, Some(Downmix::Sum { headroom_db: 0.0 }));
+
+    assert_eq!(channels, 1);
+    assert_close(&samples, &[3.0, 33.0, 63.0]);
+}
+
+#[test]
+fn sum_with_headroom() {
+    let (channels, samples) = map(Some(&[1, 2]), Some(Downmix::Sum { headroom_db: 6.0 }));
+    let gain = 0.501_187;
+
+    assert_eq!(channels, 1);
+    assert_close(&samples, &[3.0 * gain, 23.0 * gain, 43.0 * gain]);
+}
+
+#[test]
+#[should_panic(expected = "invalid channel selection")]
+fn missing_channel() {
+    ChannelMap::new(3, Some(&[3]), None);
+}
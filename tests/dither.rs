@@ -0,0 +1,73 @@
+use std::io::Cursor;
+
+use record_wav::{
+    dither::{Dither, NoiseShaping},
+    wav::{WavFile, WavFormat},
+    writer::WavWriter,
+};
+
+fn dither(shaping: NoiseShaping, seed: u64, input: &[f32], bits: u16) -> Vec<f32> {
+    let mut dither = Dither::new(2, shaping, seed);
+    input.iter().map(|&s| dither.process(s, bits)).collect()
+}
+
+fn input() -> Vec<f32> {
+    (0..10000).map(|i| (i as f32 * 0.01).sin() * 0.25).collect()
+}
+
+#[test]
+fn same_seed_same_output() {
+    for shaping in [
+        NoiseShaping::None,
+        NoiseShaping::FirstOrder,
+        NoiseShaping::Lipshitz,
+    ] {
+        let a = dither(shaping, 42, &input(), 16);
+        let b = dither(shaping, 42, &input(), 16);
+        let c = dither(shaping, 43, &input(), 16);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
+
+#[test]
+fn output_is_on_the_grid() {
+    for bits in [16, 24] {
+        let scale = (1i64 << (bits - 1)) as f32;
+        for sample in dither(NoiseShaping::Lipshitz, 1, &input(), bits) {
+            let value = sample * scale;
+            assert_eq!(value, value.round());
+            assert!((-scale..scale).contains(&value));
+        }
+    }
+}
+
+#[test]
+fn unbiased() {
+    // A level between two steps comes out as that level on average
+    let level = 100.3 / 32768.0;
+    let output = dither(NoiseShaping::None, 7, &vec![level; 100000], 16);
+
+    let mean = output.iter().map(|&s| s as f64).sum::<f64>() / output.len() as f64;
+    assert!(
+        (mean * 32768.0 - 100.3).abs() < 0.01,
+        "mean of {}",
+        mean * 32768.0
+    );
+}
+
+#[test]
+fn files_are_reproducible() {
+    let write = |seed| {
+        let file = WavFile::new(WavFormat::Pcm, 2, 48000, 16);
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), file)
+            .unwrap()
+            .with_dither(Dither::new(2, NoiseShaping::FirstOrder, seed));
+        writer.write_samples(&input()).unwrap();
+        writer.finalize().unwrap().into_inner()
+    };
+
+    assert_eq!(write(5), write(5));
+    assert_ne!(write(5), write(6));
+}
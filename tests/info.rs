@@ -0,0 +1,76 @@
+use std::io::Cursor;
+
+use record_wav::{
+    info::Info,
+    reader::WavReader,
+    serialize::BinarySerialize,
+    wav::{WavFile, WavFormat},
+    writer::WavWriter,
+};
+
+fn info() -> Info {
+    Info {
+        // Odd and even lengths once NUL terminated
+        title: Some("Take".to_string()),
+        artist: Some("abc".to_string()),
+        comment: None,
+        creation_date: Some("2024-05-01".to_string()),
+        software: Some("record-wav".to_string()),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut file = WavFile::new(WavFormat::Pcm, 1, 48000, 16);
+    file.info = info();
+    let mut writer = WavWriter::new(Cursor::new(Vec::new()), file).unwrap();
+    writer.write_samples(&[1i16, 2, 3]).unwrap();
+    let bytes = writer.finalize().unwrap().into_inner();
+
+    let mut reader = WavReader::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(reader.file().info, info());
+    assert_eq!(reader.read_samples::<i16>().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn odd_lengths_are_padded() {
+    let info = Info {
+        title: Some("Take".to_string()),
+        artist: Some("abc".to_string()),
+        ..Info::default()
+    };
+    let mut bytes = vec![0u8; info.needed_size()];
+    info.serialize(&mut bytes).unwrap();
+
+    let expected: &[u8] = b"LIST\x1e\0\0\0INFO\
+        INAM\x05\0\0\0Take\0\0\
+        IART\x04\0\0\0abc\0";
+    assert_eq!(bytes, expected);
+    assert_eq!(Info::parse(&bytes[12..]), info);
+}
+
+#[test]
+fn empty_info_is_not_written() {
+    assert_eq!(Info::default().needed_size(), 0);
+
+    let file = WavFile::new(WavFormat::Pcm, 1, 48000, 16);
+    let bytes = WavWriter::new(Cursor::new(Vec::new()), file)
+        .unwrap()
+        .finalize()
+        .unwrap()
+        .into_inner();
+    assert!(!bytes.windows(4).any(|id| id == b"LIST"));
+}
+
+#[test]
+fn unknown_tags_are_skipped() {
+    let body = b"IGNR\x03\0\0\0abc\0INAM\x02\0\0\0x\0";
+
+    assert_eq!(
+        Info::parse(body),
+        Info {
+            title: Some("x".to_string()),
+            ..Info::default()
+        }
+    );
+}
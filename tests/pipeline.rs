@@ -0,0 +1,287 @@
+use std::io::Cursor;
+
+use record_wav::{
+    control::Control,
+    reader::WavReader,
+    recorder::Recorder,
+    source::{AudioSource, FileSource, MemorySource, SourceError},
+    wav::{WavFile, WavFormat},
+    writer::WavWriter,
+};
+
+const SAMPLE_RATE: u32 = 48000;
+
+// A ramp from -1 to 1, different on every channel
+fn samples(channels: u16, frames: usize) -> Vec<f32> {
+    (0..frames * channels as usize)
+        .map(|i| (i as f32 / (frames * channels as usize) as f32) * 2.0 - 1.0)
+        .collect()
+}
+
+fn writer(format: WavFormat, channels: u16, bits_per_sample: u16) -> WavWriter<Cursor<Vec<u8>>> {
+    let file = WavFile::new(format, channels, SAMPLE_RATE, bits_per_sample);
+    WavWriter::new(Cursor::new(Vec::new()), file).unwrap()
+}
+
+fn run(mut recorder: Recorder<Cursor<Vec<u8>>>, samples: Vec<f32>, channels: u16) -> Vec<Vec<u8>> {
+    let mut source = MemorySource::new(channels, SAMPLE_RATE, samples);
+    recorder.run(&mut source, &Control::new()).unwrap();
+
+    recorder
+        .finish()
+        .unwrap()
+        .into_iter()
+        .map(Cursor::into_inner)
+        .collect()
+}
+
+// Reads a file back through a `FileSource`, so as `f32` whatever the format
+fn read(bytes: Vec<u8>) -> (u16, Vec<f32>) {
+    let mut source = FileSource::new(WavReader::new(Cursor::new(bytes)).unwrap());
+    let mut samples = Vec::new();
+    let mut buffer = [0.0; 1000];
+    while let Some(len) = source.read(&mut buffer).unwrap() {
+        samples.extend_from_slice(&buffer[..len]);
+    }
+
+    (source.channels(), samples)
+}
+
+fn round_trip(format: WavFormat, bits_per_sample: u16, tolerance: f32) {
+    let input = samples(2, 5000);
+    let recorder = Recorder::new(writer(format, 2, bits_per_sample), 2);
+    let files = run(recorder, input.clone(), 2);
+
+    let reader = WavReader::new(Cursor::new(files[0].clone())).unwrap();
+    assert_eq!(reader.file().format, format);
+    assert_eq!(reader.file().bits_per_sample, bits_per_sample);
+    assert_eq!(reader.file().sample_rate, SAMPLE_RATE);
+    assert_eq!(reader.file().frames(), 5000);
+
+    let (channels, output) = read(files[0].clone());
+    assert_eq!(channels, 2);
+    assert_eq!(output.len(), input.len());
+    for (a, b) in input.iter().zip(&output) {
+        assert!((a - b).abs() <= tolerance, "{} became {}", a, b);
+    }
+}
+
+#[test]
+fn round_trip_pcm_16() {
+    round_trip(WavFormat::Pcm, 16, 1.0 / 32768.0);
+}
+
+#[test]
+fn round_trip_pcm_24() {
+    round_trip(WavFormat::Pcm, 24, 1.0 / 8388608.0);
+}
+
+#[test]
+fn round_trip_pcm_32() {
+    round_trip(WavFormat::Pcm, 32, 1e-7);
+}
+
+#[test]
+fn round_trip_float() {
+    round_trip(WavFormat::IeeeFloat, 32, 0.0);
+}
+
+#[test]
+fn split() {
+    let input = samples(3, 3000);
+    let writers = (0..3)
+        .map(|_| writer(WavFormat::IeeeFloat, 1, 32))
+        .collect();
+    let files = run(Recorder::split(writers), input.clone(), 3);
+
+    assert_eq!(files.len(), 3);
+    for (channel, file) in files.into_iter().enumerate() {
+        let (channels, output) = read(file);
+        let expected = input
+            .iter()
+            .skip(channel)
+            .step_by(3)
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(channels, 1);
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn max_frames() {
+    let input = samples(2, 10000);
+    let mut recorder =
+        Recorder::new(writer(WavFormat::IeeeFloat, 2, 32), 2).with_max_frames(Some(4321));
+    let mut source = MemorySource::new(2, SAMPLE_RATE, input.clone());
+    recorder.run(&mut source, &Control::new()).unwrap();
+
+    assert!(recorder.is_complete());
+    assert_eq!(recorder.frames(), 4321);
+    let file = recorder.finish().unwrap().remove(0).into_inner();
+    let (_, output) = read(file);
+    assert_eq!(output, input[..4321 * 2]);
+}
+
+#[test]
+fn stopped_before_start() {
+    let control = Control::new();
+    control.stop();
+    let mut recorder = Recorder::new(writer(WavFormat::Pcm, 1, 16), 1);
+    let mut source = MemorySource::new(1, SAMPLE_RATE, samples(1, 1000));
+    recorder.run(&mut source, &control).unwrap();
+
+    assert_eq!(recorder.frames(), 0);
+    let file = recorder.finish().unwrap().remove(0).into_inner();
+    assert_eq!(read(file).1.len(), 0);
+}
+
+// Plays frame numbers as samples, acting on `control` once given frames have been read
+struct ScriptedSource<'a> {
+    control: &'a Control,
+    position: u64,
+    frames: u64,
+    markers: Vec<u64>,
+    arm_at: u64,
+    // Fails once all the frames were read, like an unplugged device
+    disconnect: bool,
+}
+
+impl<'a> ScriptedSource<'a> {
+    fn new(control: &'a Control, frames: u64, markers: Vec<u64>, arm_at: u64) -> Self {
+        Self {
+            control,
+            position: 0,
+            frames,
+            markers,
+            arm_at,
+            disconnect: false,
+        }
+    }
+}
+
+impl AudioSource for ScriptedSource<'_> {
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn read(&mut self, buffer: &mut [f32]) -> Result<Option<usize>, SourceError> {
+        let len = (self.frames - self.position)
+            .min(buffer.len() as u64)
+            .min(10);
+        if len == 0 && self.disconnect {
+            return Err(SourceError::Disconnected);
+        }
+        if len == 0 {
+            return Ok(None);
+        }
+        for (i, sample) in buffer[..len as usize].iter_mut().enumerate() {
+            *sample = (self.position + i as u64) as f32;
+        }
+        self.position += len;
+
+        for _ in self
+            .markers
+            .extract_if(.., |&mut frame| frame <= self.position)
+        {
+            self.control.add_marker(None);
+        }
+        if self.position == self.arm_at {
+            self.control.arm();
+        }
+
+        Ok(Some(len as usize))
+    }
+}
+
+// Cue positions of the first `cue ` chunk
+fn cues(file: &[u8]) -> Vec<u32> {
+    let Some(start) = file.windows(4).position(|id| id == b"cue ") else {
+        return Vec::new();
+    };
+    let count = u32::from_le_bytes(file[start + 8..start + 12].try_into().unwrap());
+    (0..count as usize)
+        .map(|i| {
+            let offset = start + 12 + i * 24 + 20;
+            u32::from_le_bytes(file[offset..offset + 4].try_into().unwrap())
+        })
+        .collect()
+}
+
+fn record_pre_roll(markers: Vec<u64>) -> Vec<u8> {
+    let control = Control::new();
+    let mut source = ScriptedSource::new(&control, 1000, markers, 500);
+    let mut recorder =
+        Recorder::new(writer(WavFormat::IeeeFloat, 1, 32), 1).with_pre_roll(Some(50));
+    recorder.run(&mut source, &control).unwrap();
+    assert_eq!(recorder.pre_roll_frames(), 50);
+
+    recorder.finish().unwrap().remove(0).into_inner()
+}
+
+#[test]
+fn pre_roll() {
+    let file = record_pre_roll(Vec::new());
+    let (_, output) = read(file);
+
+    let expected = (450..1000).map(|frame| frame as f32).collect::<Vec<_>>();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn markers_while_standing_by() {
+    // Older than the pre-roll, within it, then after arming
+    let file = record_pre_roll(vec![200, 480, 700]);
+
+    assert_eq!(cues(&file), [30, 250]);
+}
+
+#[test]
+fn new_source_while_standing_by() {
+    let control = Control::new();
+    let mut recorder =
+        Recorder::new(writer(WavFormat::IeeeFloat, 1, 32), 1).with_pre_roll(Some(50));
+    let mut first = ScriptedSource::new(&control, 300, vec![280], u64::MAX);
+    first.disconnect = true;
+    assert!(recorder.run(&mut first, &control).is_err());
+
+    // Armed before the new source filled the pre-roll
+    let mut second = ScriptedSource::new(&control, 100, Vec::new(), 20);
+    recorder.run(&mut second, &control).unwrap();
+    let file = recorder.finish().unwrap().remove(0).into_inner();
+
+    assert_eq!(cues(&file), []);
+    let expected = (0..100).map(|frame| frame as f32).collect::<Vec<_>>();
+    assert_eq!(read(file).1, expected);
+}
+
+#[test]
+fn no_gap_while_standing_by() {
+    let control = Control::new();
+    let mut recorder =
+        Recorder::new(writer(WavFormat::IeeeFloat, 1, 32), 1).with_pre_roll(Some(50));
+    recorder.insert_gap(300, true).unwrap();
+
+    let mut source = ScriptedSource::new(&control, 100, Vec::new(), 60);
+    recorder.run(&mut source, &control).unwrap();
+    assert!(recorder.report().gaps.is_empty());
+    let file = recorder.finish().unwrap().remove(0).into_inner();
+
+    assert_eq!(cues(&file), []);
+    let expected = (10..100).map(|frame| frame as f32).collect::<Vec<_>>();
+    assert_eq!(read(file).1, expected);
+}
+
+#[test]
+fn empty_gap() {
+    let mut recorder = Recorder::new(writer(WavFormat::IeeeFloat, 1, 32), 1);
+    recorder.insert_gap(0, true).unwrap();
+
+    assert!(recorder.report().gaps.is_empty());
+    let file = recorder.finish().unwrap().remove(0).into_inner();
+    assert_eq!(cues(&file), []);
+}
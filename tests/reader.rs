@@ -0,0 +1,92 @@
+use std::io::Cursor;
+
+use record_wav::reader::{WavError, WavReader};
+
+// A RIFF/WAVE file with a 16-byte PCM `fmt ` chunk and `data` as its samples
+fn wav(channels: u16, bits_per_sample: u16, block_align: u16, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(4 + 24 + 8 + data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&48000u32.to_le_bytes());
+    bytes.extend_from_slice(&(48000 * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&bits_per_sample.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+fn open(bytes: Vec<u8>) -> Result<WavReader<Cursor<Vec<u8>>>, WavError> {
+    WavReader::new(Cursor::new(bytes))
+}
+
+#[test]
+fn reads_samples() {
+    let data = [1i16, -2, 3, -4]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect::<Vec<_>>();
+    let mut reader = open(wav(2, 16, 4, &data)).unwrap();
+
+    assert_eq!(reader.file().channels, 2);
+    assert_eq!(reader.file().frames(), 2);
+    assert_eq!(reader.read_samples::<i16>().unwrap(), [1, -2, 3, -4]);
+}
+
+#[test]
+fn block_align_product_overflowing_16_bits() {
+    let result = open(wav(8192, 8, 0, &[]));
+
+    assert!(matches!(result, Err(WavError::InvalidFmt(_))));
+}
+
+#[test]
+fn many_channels() {
+    let reader = open(wav(4096, 16, 8192, &[0; 8192 * 3])).unwrap();
+
+    assert_eq!(reader.file().frames(), 3);
+}
+
+#[test]
+fn frame_size_out_of_range() {
+    let result = open(wav(40000, 16, 0, &[]));
+
+    assert!(matches!(result, Err(WavError::InvalidFmt(_))));
+}
+
+#[test]
+fn wrong_block_align() {
+    let result = open(wav(2, 16, 2, &[]));
+
+    assert!(matches!(result, Err(WavError::InvalidFmt(_))));
+}
+
+#[test]
+fn not_riff() {
+    let mut bytes = wav(1, 16, 2, &[]);
+    bytes[0..4].copy_from_slice(b"RIFX");
+
+    assert!(matches!(open(bytes), Err(WavError::NotRiff)));
+}
+
+#[test]
+fn missing_data() {
+    let mut bytes = wav(1, 16, 2, &[]);
+    bytes.truncate(bytes.len() - 8);
+
+    assert!(matches!(open(bytes), Err(WavError::MissingData)));
+}
+
+#[test]
+fn truncated_fmt() {
+    let mut bytes = wav(1, 16, 2, &[]);
+    bytes.truncate(30);
+
+    assert!(matches!(open(bytes), Err(WavError::Truncated)));
+}
@@ -0,0 +1,105 @@
+use std::f64::consts::PI;
+
+use record_wav::{
+    resample::{Quality, ResampledSource, Resampler},
+    source::{AudioSource, MemorySource},
+};
+
+fn sine(frequency: f64, sample_rate: u32, frames: usize) -> Vec<f32> {
+    (0..frames)
+        .map(|i| (0.5 * (2.0 * PI * frequency * i as f64 / sample_rate as f64).sin()) as f32)
+        .collect()
+}
+
+// Feeds `input` in uneven blocks, like a live source would
+fn resample(input: &[f32], channels: u16, from: u32, to: u32, quality: Quality) -> Vec<f32> {
+    let mut resampler = Resampler::new(channels, from, to, quality);
+    let mut output = Vec::new();
+    for block in input.chunks(channels as usize * 777) {
+        resampler.process(block, &mut output);
+    }
+    resampler.flush(&mut output);
+    output
+}
+
+fn rms(samples: &[f32]) -> f64 {
+    let squares: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (squares / samples.len() as f64).sqrt()
+}
+
+#[test]
+fn output_length() {
+    for (from, to) in [
+        (48000, 44100),
+        (44100, 48000),
+        (8000, 48000),
+        (48000, 16000),
+    ] {
+        for quality in [Quality::Low, Quality::Medium, Quality::High] {
+            let frames = 10007u64;
+            let output = resample(&vec![0.0; frames as usize * 2], 2, from, to, quality);
+
+            let expected = (frames * to as u64).div_ceil(from as u64);
+            assert_eq!(output.len() as u64, expected * 2, "{} -> {} Hz", from, to);
+        }
+    }
+}
+
+#[test]
+fn passband_tone_is_preserved() {
+    let input = sine(1000.0, 48000, 48000);
+    let output = resample(&input, 1, 48000, 44100, Quality::High);
+    let expected = sine(1000.0, 44100, 44100);
+
+    // Away from the ends, where the filter sees the silence around the input
+    let error = output[1000..43000]
+        .iter()
+        .zip(&expected[1000..43000])
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max);
+    assert!(error < 1e-4, "error of {}", error);
+}
+
+#[test]
+fn passband_edge_keeps_its_level() {
+    let input = sine(18000.0, 48000, 48000);
+    let output = resample(&input, 1, 48000, 44100, Quality::High);
+
+    let ratio = rms(&output[1000..43000]) / rms(&input[1000..47000]);
+    assert!((ratio - 1.0).abs() < 0.01, "level ratio of {}", ratio);
+}
+
+#[test]
+fn stopband_tone_is_removed() {
+    // Above the Nyquist frequency of the output, it would alias to 18 kHz
+    let input = sine(30000.0, 96000, 96000);
+    let output = resample(&input, 1, 96000, 48000, Quality::High);
+
+    let ratio = rms(&output[1000..47000]) / rms(&input);
+    assert!(ratio < 1e-4, "level ratio of {}", ratio);
+}
+
+#[test]
+fn channels_stay_apart() {
+    let left = sine(440.0, 44100, 4410);
+    let input = left.iter().flat_map(|&s| [s, 0.0]).collect::<Vec<_>>();
+    let output = resample(&input, 2, 44100, 48000, Quality::Medium);
+
+    assert!(output.iter().skip(1).step_by(2).all(|&s| s == 0.0));
+    assert!(rms(&output.iter().step_by(2).copied().collect::<Vec<_>>()) > 0.3);
+}
+
+#[test]
+fn resampled_source() {
+    let inner = MemorySource::new(1, 44100, sine(440.0, 44100, 44100));
+    let mut source = ResampledSource::new(Box::new(inner), 48000, Quality::High);
+    assert_eq!(source.sample_rate(), 48000);
+    assert_eq!(source.channels(), 1);
+
+    let mut frames = 0;
+    let mut buffer = [0.0; 1000];
+    while let Some(len) = source.read(&mut buffer).unwrap() {
+        frames += len;
+    }
+    assert_eq!(frames, 48000);
+}
@@ -0,0 +1,77 @@
+use std::io::Cursor;
+
+use record_wav::{
+    reader::{WavError, WavReader},
+    serialize::BinarySerialize,
+    wav::{WavFile, WavFormat},
+};
+
+const DATA_SIZE: u64 = 5 << 30;
+
+fn header(file: &WavFile) -> Vec<u8> {
+    let mut bytes = vec![0u8; file.needed_size()];
+    file.serialize(&mut bytes).unwrap();
+    bytes
+}
+
+fn large_file() -> WavFile {
+    let mut file = WavFile::new(WavFormat::Pcm, 1, 48000, 16);
+    file.data_size = DATA_SIZE;
+    file
+}
+
+#[test]
+fn small_file_keeps_riff() {
+    let file = WavFile::new(WavFormat::Pcm, 1, 48000, 16);
+    let bytes = header(&file);
+
+    assert!(!file.is_rf64());
+    assert_eq!(&bytes[0..4], b"RIFF");
+    // The reserved room is a `JUNK` chunk
+    assert_eq!(&bytes[12..16], b"JUNK");
+}
+
+#[test]
+fn large_file_becomes_rf64() {
+    let file = large_file();
+    let bytes = header(&file);
+
+    assert!(file.is_rf64());
+    assert_eq!(&bytes[0..4], b"RF64");
+    assert_eq!(&bytes[4..8], &u32::MAX.to_le_bytes());
+    assert_eq!(&bytes[12..16], b"ds64");
+    assert_eq!(&bytes[20..28], &file.riff_size().to_le_bytes());
+    assert_eq!(&bytes[28..36], &DATA_SIZE.to_le_bytes());
+    assert_eq!(&bytes[36..44], &(DATA_SIZE / 2).to_le_bytes());
+    assert_eq!(&bytes[bytes.len() - 4..], &u32::MAX.to_le_bytes());
+}
+
+#[test]
+fn large_file_needs_reserved_ds64() {
+    let mut file = large_file();
+    file.reserve_ds64 = false;
+
+    assert!(file.serialize(&mut vec![0u8; file.needed_size()]).is_err());
+}
+
+#[test]
+fn reads_rf64() {
+    let mut bytes = header(&large_file());
+    // Only the start of the samples made it to the file
+    bytes.extend([1i16, 2, 3, 4].iter().flat_map(|s| s.to_le_bytes()));
+    let mut reader = WavReader::new(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(reader.file().data_size, 8);
+    assert_eq!(reader.read_samples::<i16>().unwrap(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn rf64_without_ds64() {
+    let mut bytes = header(&large_file());
+    bytes[12..16].copy_from_slice(b"JUNK");
+
+    assert!(matches!(
+        WavReader::new(Cursor::new(bytes)),
+        Err(WavError::InvalidDs64)
+    ));
+}
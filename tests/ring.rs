@@ -0,0 +1,87 @@
+use std::thread;
+
+use record_wav::ring::ring_buffer;
+
+fn ramp(start: usize, len: usize) -> Vec<f32> {
+    (start..start + len).map(|i| i as f32).collect()
+}
+
+#[test]
+fn wraps_around() {
+    let (mut producer, mut consumer) = ring_buffer(7);
+    let mut buffer = [0.0; 7];
+    let mut next = 0;
+
+    // Blocks of 5 in 7 slots start at every offset in turn
+    for _ in 0..20 {
+        assert!(producer.push(ramp(next, 5)));
+        assert_eq!(producer.free(), 2);
+        assert_eq!(consumer.pop(&mut buffer), 5);
+        assert_eq!(buffer[..5], ramp(next, 5));
+        next += 5;
+    }
+    assert_eq!(producer.free(), 7);
+}
+
+#[test]
+fn drops_whole_blocks_on_overflow() {
+    let (mut producer, mut consumer) = ring_buffer(10);
+    assert!(producer.push(ramp(0, 6)));
+    assert!(!producer.push(ramp(6, 6)));
+    assert!(producer.push(ramp(12, 4)));
+    assert!(!producer.push(ramp(16, 1)));
+
+    assert_eq!(consumer.take_dropped(), 7);
+    assert_eq!(consumer.take_dropped(), 0);
+
+    let mut buffer = [0.0; 16];
+    assert_eq!(consumer.pop(&mut buffer), 10);
+    let expected = [ramp(0, 6), ramp(12, 4)].concat();
+    assert_eq!(buffer[..10], expected);
+}
+
+#[test]
+fn pops_into_a_smaller_buffer() {
+    let (mut producer, mut consumer) = ring_buffer(16);
+    assert!(producer.push(ramp(0, 10)));
+
+    let mut buffer = [0.0; 4];
+    assert_eq!(consumer.pop(&mut buffer), 4);
+    assert_eq!(buffer, *ramp(0, 4));
+    assert_eq!(consumer.pop(&mut buffer), 4);
+    assert_eq!(buffer, *ramp(4, 4));
+    assert_eq!(consumer.pop(&mut buffer), 2);
+    assert_eq!(buffer[..2], ramp(8, 2));
+    assert_eq!(consumer.pop(&mut buffer), 0);
+    assert_eq!(producer.free(), 16);
+}
+
+#[test]
+fn across_threads() {
+    let (mut producer, mut consumer) = ring_buffer(64);
+    let total = 10_000;
+
+    let producing = thread::spawn(move || {
+        let mut next = 0;
+        while next < total {
+            if producer.push(ramp(next, 10)) {
+                next += 10;
+            } else {
+                thread::yield_now();
+            }
+        }
+    });
+
+    let mut received = Vec::with_capacity(total);
+    let mut buffer = [0.0; 32];
+    while received.len() < total {
+        let len = consumer.pop(&mut buffer);
+        received.extend_from_slice(&buffer[..len]);
+        if len == 0 {
+            thread::yield_now();
+        }
+    }
+    producing.join().unwrap();
+
+    assert_eq!(received, ramp(0, total));
+}
@@ -0,0 +1,103 @@
+use std::io::Cursor;
+
+use record_wav::{
+    reader::WavReader,
+    sample::I24,
+    serialize::{BinaryDeserialize, BinarySerialize},
+    wav::{WavFile, WavFormat, SUBFORMAT_GUID_TAIL, WAVE_FORMAT_EXTENSIBLE},
+    writer::WavWriter,
+};
+
+fn header(file: &WavFile) -> Vec<u8> {
+    let mut bytes = vec![0u8; file.needed_size()];
+    file.serialize(&mut bytes).unwrap();
+    bytes
+}
+
+// The `fmt ` chunk follows the RIFF header and the reserved `JUNK` chunk
+fn fmt_chunk(bytes: &[u8]) -> &[u8] {
+    assert_eq!(&bytes[48..52], b"fmt ");
+    let size = u32::deserialize(&bytes[52..56]).unwrap() as usize;
+    &bytes[56..56 + size]
+}
+
+#[test]
+fn plain_fmt() {
+    let file = WavFile::new(WavFormat::Pcm, 2, 48000, 16);
+    let bytes = header(&file);
+    let fmt = fmt_chunk(&bytes);
+
+    assert!(!file.is_extensible());
+    assert_eq!(fmt.len(), 16);
+    assert_eq!(u16::deserialize(&fmt[0..2]).unwrap(), 1);
+}
+
+#[test]
+fn extensible_fmt() {
+    let mut file = WavFile::new(WavFormat::Pcm, 4, 48000, 24);
+    file.channel_mask = 0x107;
+    let bytes = header(&file);
+    let fmt = fmt_chunk(&bytes);
+
+    assert!(file.is_extensible());
+    assert_eq!(fmt.len(), 40);
+    assert_eq!(
+        u16::deserialize(&fmt[0..2]).unwrap(),
+        WAVE_FORMAT_EXTENSIBLE
+    );
+    assert_eq!(u16::deserialize(&fmt[2..4]).unwrap(), 4);
+    assert_eq!(u32::deserialize(&fmt[4..8]).unwrap(), 48000);
+    assert_eq!(u32::deserialize(&fmt[8..12]).unwrap(), 48000 * 12);
+    assert_eq!(u16::deserialize(&fmt[12..14]).unwrap(), 12);
+    assert_eq!(u16::deserialize(&fmt[14..16]).unwrap(), 24);
+    // cbSize, valid bits and channel mask
+    assert_eq!(u16::deserialize(&fmt[16..18]).unwrap(), 22);
+    assert_eq!(u16::deserialize(&fmt[18..20]).unwrap(), 24);
+    assert_eq!(u32::deserialize(&fmt[20..24]).unwrap(), 0x107);
+    // Subformat GUID
+    assert_eq!(u16::deserialize(&fmt[24..26]).unwrap(), 1);
+    assert_eq!(fmt[26..40], SUBFORMAT_GUID_TAIL);
+}
+
+#[test]
+fn extensible_float_fmt() {
+    let file = WavFile::new(WavFormat::IeeeFloat, 3, 48000, 32);
+    let bytes = header(&file);
+    let fmt = fmt_chunk(&bytes);
+
+    assert_eq!(fmt.len(), 40);
+    assert_eq!(u16::deserialize(&fmt[24..26]).unwrap(), 3);
+    // Non-PCM formats still get their `fact` chunk
+    assert_eq!(&bytes[96..100], b"fact");
+}
+
+#[test]
+fn reads_extensible_fmt() {
+    let mut file = WavFile::new(WavFormat::Pcm, 4, 48000, 24);
+    file.channel_mask = 0x107;
+    let mut writer = WavWriter::new(Cursor::new(Vec::new()), file).unwrap();
+    writer
+        .write_samples(&[I24(1), I24(-2), I24(3), I24(-4)])
+        .unwrap();
+    let bytes = writer.finalize().unwrap().into_inner();
+
+    let mut reader = WavReader::new(Cursor::new(bytes)).unwrap();
+    let file = reader.file();
+    assert_eq!(file.format, WavFormat::Pcm);
+    assert_eq!(file.channels, 4);
+    assert_eq!(file.bits_per_sample, 24);
+    assert_eq!(file.channel_mask, 0x107);
+    assert_eq!(file.frames(), 1);
+    assert_eq!(
+        reader.read_samples::<I24>().unwrap(),
+        [I24(1), I24(-2), I24(3), I24(-4)]
+    );
+}
+
+#[test]
+fn frame_size_overflowing_16_bits() {
+    let file = WavFile::new(WavFormat::Pcm, 40000, 48000, 16);
+
+    assert_eq!(file.frame_size(), None);
+    assert!(file.serialize(&mut vec![0u8; file.needed_size()]).is_err());
+}